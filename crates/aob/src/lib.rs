@@ -14,25 +14,39 @@
 #![warn(clippy::pedantic)]
 
 pub use aob_common::{
+    Capture,
+    ConversionError,
+    Dialect,
     DynamicNeedle,
     Error,
+    Feature,
     Find,
+    FindNonOverlapping,
+    FindSet,
     Match,
     Method,
     Needle,
+    NeedleSet,
     Reason,
+    Signature,
     StaticNeedle,
+    Uniqueness,
+    Unrepresentable,
 };
 pub use aob_macros::aob;
 
 #[cfg(test)]
+#[expect(clippy::needless_pass_by_value, clippy::unreadable_literal)]
 mod tests {
     use crate::{
         aob,
         DynamicNeedle,
         Method,
         Needle,
+        NeedleSet,
+        Uniqueness,
     };
+    use std::ops::Range;
 
     #[test]
     fn test_aob() {
@@ -43,6 +57,35 @@ mod tests {
             pub(super) const _4 = ida("11 ? 22");
             const _5 = ida("11");
             const _6 = ida("?");
+            const _7 = ida("1? ?2 ??");
+            const _8 = code(b"\x11\x00\x22", "x?x");
+            const _9 = code(b"", "");
+            const _10 = yara("{ 11 [2-4] ( 22 | 33 ?4 ) [-] 55 }");
+            const _11 = yara("11 [1-] 22");
+            const _12 = yara("( 11 | 22 [1-2] 33 )");
+            const _13 = ida("11 [2-4] 22 ? 33");
+            const _14 = ida("? [0-1] ?");
+            const _15 = ida("(E8|E9) ? ? ? ? (00-1F|7F)");
+            const _16 = ida("!CC 8B !(00|FF)");
+            const _17 = ida("48 8B 05 (disp: ? ? ? ?)");
+            const _18 = ida("(E8) (? [0-4] (?))");
+            const _19 = ida("48 8D 0D ? ? ? ? ^ E8");
+            const _20 = ida("? [1-2] (^ ?)");
+            const _21 = ida("E8 ?{4} CC{8} (00-1F){2}");
+            const _23 = ida("?<a> ? ?<a> [1-2] ?<b>{2}");
+            const _22 = ida("
+                48 8B 05 ? ? ? ?    ; mov rax, [rip+disp]
+                FF 50 ?             // call qword ptr [rax+?]
+            ");
+            const _24 = ida("(11 22|33) ? (44|55 [1-2])");
+            const _25 = array("{ 0x11, ?, 0x22 }");
+            const _26 = array("[]");
+            const _27 = bytes(b"Error: \\?\\? not found");
+            const _28 = bytes(br"\?\\");
+            const _29 = ida(r#"48 8D 0D ? ? ? ? "MZ" 00 u"Config""#);
+            const _30 = ida("u32le(0xDEADBEEF) f32(1.5) i16be(-2) u64(?) u16(0x?1?2)");
+            const _31 = ida("u32le(0x40..=0x400) [0-4] f64be(..-0.5)");
+            const _32 = ida("11 [1000] 22");
         }
     }
 
@@ -100,6 +143,345 @@ mod tests {
         }};
     }
 
+    macro_rules! do_test_ranges {
+        ($method:ident, $pattern:literal, $match_count:literal, [$($ranges:tt)*]) => {{
+            let ranges: &[Range<usize>] = &[$($ranges)*];
+
+            let needle = do_test_ranges!(@dyn $method, $pattern);
+            let matches: Vec<_> = needle.find_iter(MOBY_DICK).map(|x| x.range()).collect();
+            assert_eq!(matches.len(), $match_count, "dyn = {}", stringify!($pattern));
+            assert_eq!(matches[..ranges.len()], *ranges, "dyn = {}", stringify!($pattern));
+            let mut reversed: Vec<_> = needle.rfind_iter(MOBY_DICK).map(|x| x.range()).collect();
+            reversed.reverse();
+            assert_eq!(reversed, matches, "dyn rev = {}", stringify!($pattern));
+
+            aob! { const NEEDLE = $method($pattern); }
+            let matches: Vec<_> = NEEDLE.find_iter(MOBY_DICK).map(|x| x.range()).collect();
+            assert_eq!(matches.len(), $match_count, "const = {}", stringify!($pattern));
+            assert_eq!(matches[..ranges.len()], *ranges, "const = {}", stringify!($pattern));
+            let mut reversed: Vec<_> = NEEDLE.rfind_iter(MOBY_DICK).map(|x| x.range()).collect();
+            reversed.reverse();
+            assert_eq!(reversed, matches, "const rev = {}", stringify!($pattern));
+        }};
+        (@dyn ida, $pattern:literal) => {
+            DynamicNeedle::from_ida($pattern).unwrap()
+        };
+        (@dyn yara, $pattern:literal) => {
+            DynamicNeedle::from_yara($pattern).unwrap()
+        };
+        (@dyn array, $pattern:literal) => {
+            DynamicNeedle::from_array_literal($pattern).unwrap()
+        };
+        (@dyn bytes, $pattern:literal) => {
+            DynamicNeedle::from_byte_string($pattern).unwrap()
+        };
+    }
+
+    #[test]
+    fn test_gaps() {
+        do_test_ranges!(
+            ida,
+            "41 68 61 62 [0-20] 77 68 61 6C 65",
+            3,
+            [404628..404646, 1229122..1229141]
+        );
+        do_test_ranges!(
+            ida,
+            "77 68 69 74 65 [1-3] 77 68 61 6C 65",
+            32,
+            [305002..305013, 373416..373428]
+        );
+    }
+
+    #[test]
+    fn test_sets() {
+        do_test_ranges!(ida, "(57|77) 68 61 6C 65", 1693, [1331..1336, 1749..1754]);
+        do_test_ranges!(
+            ida,
+            "(41-5A|61-7A) 68 61 6C 65",
+            1703,
+            [1331..1336, 1749..1754]
+        );
+    }
+
+    #[test]
+    fn test_negation() {
+        do_test_ranges!(ida, "!20 77 68 61 6C 65", 146, [10077..10083, 10587..10593]);
+    }
+
+    #[test]
+    fn test_repetition() {
+        do_test_ranges!(ida, "20 ?{3} 6C 65 20", 816, [2167..2174, 4093..4100]);
+        do_test_ranges!(ida, "65{2} !20{2}", 2530, [290..294, 476..480]);
+    }
+
+    #[test]
+    fn test_named_wildcards() {
+        do_test_ranges!(ida, "?<a> ?<a> 20", 4930, [659..662, 750..753]);
+        do_test_ranges!(ida, "20 ?<a> [0-3] ?<a> 20", 3844, [2173..2179, 4850..4856]);
+    }
+
+    #[test]
+    fn test_cursor() {
+        const PATTERN: &str = "77 68 61 6C 65 [0-8] ^ 62 6F 61 74";
+        aob! { const NEEDLE = ida("77 68 61 6C 65 [0-8] ^ 62 6F 61 74"); }
+        let needle = DynamicNeedle::from_ida(PATTERN).unwrap();
+        let matches: Vec<_> = needle
+            .find_iter(MOBY_DICK)
+            .map(|x| (x.range(), x.cursor()))
+            .collect();
+        let expected: Vec<_> = NEEDLE
+            .find_iter(MOBY_DICK)
+            .map(|x| (x.range(), x.cursor()))
+            .collect();
+        assert_eq!(matches, expected, "{PATTERN}");
+        assert_eq!(matches.len(), 33, "{PATTERN}");
+        for (range, cursor) in matches {
+            assert_eq!(cursor, range.end - 4, "{PATTERN}");
+        }
+    }
+
+    #[test]
+    fn test_captures() {
+        const PATTERN: &str = "(first: 77 68 ?) [0-4] (last: 62 6F 61 74)";
+        const FIXED: &str = "20 (77 68 61 6C (65)) 73";
+
+        macro_rules! collect_captures {
+            ($needle:expr) => {
+                $needle
+                    .find_iter(MOBY_DICK)
+                    .map(|x| {
+                        let captures: Vec<_> = x.captures().map(|x| x.range()).collect();
+                        (x.range(), captures)
+                    })
+                    .collect::<Vec<_>>()
+            };
+        }
+
+        aob! { const NEEDLE = ida("(first: 77 68 ?) [0-4] (last: 62 6F 61 74)"); }
+        let needle = DynamicNeedle::from_ida(PATTERN).unwrap();
+        let matches = collect_captures!(needle);
+        assert_eq!(matches, collect_captures!(NEEDLE), "{PATTERN}");
+        assert!(!matches.is_empty(), "{PATTERN}");
+        assert_eq!(needle.capture_index("first"), Some(0), "{PATTERN}");
+        assert_eq!(NEEDLE.capture_index("last"), Some(1), "{PATTERN}");
+        for (range, captures) in matches {
+            let [first, last] = &captures[..] else {
+                panic!("{captures:?}");
+            };
+            assert_eq!(first.start, range.start, "{PATTERN}");
+            assert_eq!(&MOBY_DICK[first.clone()][..2], b"wh", "{PATTERN}");
+            assert_eq!(last.end, range.end, "{PATTERN}");
+            assert_eq!(&MOBY_DICK[last.clone()], b"boat", "{PATTERN}");
+        }
+
+        aob! { const FIXED_NEEDLE = ida("20 (77 68 61 6C (65)) 73"); }
+        let needle = DynamicNeedle::from_ida(FIXED).unwrap();
+        let matches = collect_captures!(needle);
+        assert_eq!(matches, collect_captures!(FIXED_NEEDLE), "{FIXED}");
+        assert!(!matches.is_empty(), "{FIXED}");
+        for (range, captures) in matches {
+            assert_eq!(
+                captures,
+                [
+                    range.start + 1..range.start + 6,
+                    range.start + 5..range.start + 6,
+                ],
+                "{FIXED}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_array_literal() {
+        do_test_ranges!(
+            array,
+            "{ 0x77, 0x68, ?, 0x6C, 0x65 }",
+            1721,
+            [5714..5719, 6953..6958]
+        );
+    }
+
+    #[test]
+    fn test_byte_string() {
+        do_test_ranges!(bytes, br"wh\?le", 1721, [5714..5719, 6953..6958]);
+        do_test_ranges!(bytes, b"\\?\\?\\?ale", 1906, [1330..1336, 1748..1754]);
+    }
+
+    #[test]
+    fn test_strings() {
+        do_test_ranges!(ida, r#""whale""#, 1334, [5714..5719, 6953..6958]);
+        do_test_ranges!(ida, r#""wh" ? "le""#, 1721, [5714..5719, 6953..6958]);
+    }
+
+    #[test]
+    fn test_typed_values() {
+        do_test_ranges!(ida, "u32le(0x656C6168)", 1703, [1332..1336, 1750..1754]);
+        do_test_ranges!(ida, "u32be(0x7768?16C)", 1453, [5714..5718, 6953..6957]);
+    }
+
+    #[test]
+    fn test_ranges() {
+        do_test_ranges!(
+            ida,
+            r#""wh" u8(0x61..=0x65) "le""#,
+            1334,
+            [5714..5719, 6953..6958]
+        );
+        do_test_ranges!(
+            ida,
+            r#""wh" [0-1] u8(0x61..0x66) "le""#,
+            1339,
+            [5714..5719, 6953..6958]
+        );
+        do_test_ranges!(ida, "u16be(0x6861..0x6870)", 51612, [167..169, 197..199]);
+    }
+
+    #[test]
+    fn test_non_overlapping() {
+        aob! { const NEEDLE = ida("65 ? 65"); }
+        let needle = DynamicNeedle::from_ida("65 ? 65").unwrap();
+        assert_eq!(NEEDLE.find_iter(MOBY_DICK).count(), 8912);
+        for iter in [
+            NEEDLE.find_iter_non_overlapping(MOBY_DICK),
+            needle.find_iter_non_overlapping(MOBY_DICK),
+        ] {
+            let matches: Vec<_> = iter.map(|x| x.range()).collect();
+            assert_eq!(matches.len(), 8612);
+            assert!(matches.windows(2).all(|x| x[0].end <= x[1].start));
+        }
+    }
+
+    #[test]
+    fn test_aligned() {
+        aob! { const NEEDLE = ida(r#""the" [0-2] "whale""#); }
+        let needle = DynamicNeedle::from_ida(r#""the" [0-2] "whale""#).unwrap();
+        for (alignment, remainder) in [(4, 0), (8, 3)] {
+            let expected: Vec<_> = needle
+                .find_iter(MOBY_DICK)
+                .map(|x| x.start())
+                .filter(|x| x % alignment == remainder)
+                .collect();
+            assert!(!expected.is_empty());
+            for iter in [
+                NEEDLE.find_iter_aligned(MOBY_DICK, alignment, remainder),
+                needle.find_iter_aligned(MOBY_DICK, alignment, remainder),
+            ] {
+                let matches: Vec<_> = iter.map(|x| x.start()).collect();
+                assert_eq!(matches, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_unique() {
+        aob! {
+            const UNIQUE = ida(r#""Call me Ishmael""#);
+            const AMBIGUOUS = ida(r#""Loomings""#);
+            const MISSING = ida(r#""Call me Ahab""#);
+        }
+        assert!(matches!(
+            UNIQUE.find_unique(MOBY_DICK),
+            Uniqueness::Unique(found) if found.range() == (28794..28809)
+        ));
+        assert!(matches!(
+            AMBIGUOUS.find_unique(MOBY_DICK),
+            Uniqueness::Ambiguous { first, .. } if first.start() == 142
+        ));
+        assert!(matches!(
+            MISSING.find_unique(MOBY_DICK),
+            Uniqueness::NotFound
+        ));
+    }
+
+    #[test]
+    fn test_needle_set() {
+        aob! {
+            const WHALE = ida(r#""whale""#);
+            const CHAPTER = yara("{ 43 48 41 50 54 45 52 20 31 [2-] 4C 6F 6F 6D 69 6E 67 73 }");
+            const WILDCARDS = ida("? ?");
+        }
+        let boat = DynamicNeedle::from_ida("77 68 61 6C 65 [0-8] 62 6F 61 74").unwrap();
+        let ahab = DynamicNeedle::from_yara("( 41 68 61 62 | 49 73 68 6D 61 65 6C )").unwrap();
+        let needles: [&dyn Needle; 5] = [&WHALE, &boat, &CHAPTER, &ahab, &WILDCARDS];
+
+        let set = NeedleSet::new(needles);
+        let haystack = &MOBY_DICK[..200_000];
+        let mut counts = [0; 5];
+        let mut last = (0, 0);
+        for (id, found) in set.find_iter(haystack) {
+            assert!(last <= (found.start(), id));
+            last = (found.start(), id);
+            counts[id] += 1;
+        }
+        let expected = needles.map(|needle| needle.find_iter(haystack).count());
+        assert_eq!(counts, expected);
+        assert_eq!(counts[4], haystack.len() - 1);
+    }
+
+    #[test]
+    fn test_formatting() {
+        aob! {
+            const IDA = ida("77 68 61 6c 65 [0-8] (62|42) 6F ?? 74 ; boat");
+            const YARA = yara("{ ( 41 68 61 62 | 49 73 68 6D 61 65 6C ) 2? }");
+            const CODE = code(b"\x77\x68\x00\x6C\x65", "xx?xx");
+        }
+        for (formatted, expected, code_style) in [
+            (
+                IDA.to_string(),
+                "77 68 61 6C 65 [0-8] (42|62) 6F ? 74",
+                None,
+            ),
+            (
+                YARA.to_string(),
+                "(41 68 61 62|49 73 68 6D 61 65 6C) 2?",
+                None,
+            ),
+            (
+                CODE.to_string(),
+                "77 68 ? 6C 65",
+                Some((b"\x77\x68\x00\x6C\x65".to_vec(), "xx?xx".to_owned())),
+            ),
+        ] {
+            assert_eq!(formatted, expected);
+            let needle = DynamicNeedle::from_ida(&formatted).unwrap();
+            assert_eq!(needle.to_string(), expected);
+            assert_eq!(needle.to_code_style(), code_style, "{expected}");
+        }
+
+        assert_eq!(IDA.to_ida(), IDA.to_string());
+        aob! { const CAPTURES = ida("48 8B 05 (disp: ? ? ? ?) ^ ?<x> ?<x>"); }
+        assert_eq!(CAPTURES.to_ida(), "48 8B 05 (disp: ? ? ? ?) ^ ?<x> ?<x>");
+        assert_eq!(
+            CODE.to_code_style(),
+            DynamicNeedle::from_ida("77 68 ? 6C 65")
+                .unwrap()
+                .to_code_style()
+        );
+        let reparsed = DynamicNeedle::from_ida(&YARA.to_ida()).unwrap();
+        assert_eq!(
+            reparsed.find_iter(MOBY_DICK).count(),
+            YARA.find_iter(MOBY_DICK).count()
+        );
+    }
+
+    #[test]
+    fn test_yara() {
+        do_test_ranges!(
+            yara,
+            "77 68 61 6C 65 [0-8] 62 6F 61 74",
+            33,
+            [219893..219903, 220065..220075]
+        );
+        do_test_ranges!(yara, "( 41 68 61 62 | 49 73 68 6D 61 65 6C )", 536, []);
+        do_test_ranges!(
+            yara,
+            "{ 43 48 41 50 54 45 52 20 31 [2-] 4C 6F 6F 6D 69 6E 67 73 }",
+            48,
+            [131..150, 376..28789]
+        );
+    }
+
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     #[test]
     fn test_avx2() {
@@ -1,6 +1,7 @@
 use aob_common::{
     DynamicNeedle,
     Needle as _,
+    NeedleSet,
 };
 use criterion::{
     criterion_group,
@@ -121,5 +122,56 @@ fn bench_simple(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, bench_simple);
+fn bench_set(c: &mut Criterion) {
+    let haystack = include_bytes!("../../../data/moby_dick.txt");
+
+    let mut group = c.benchmark_group("set-scanning");
+    group.throughput(Throughput::Bytes(haystack.len() as u64));
+
+    for count in [4, 16, 64, 256] {
+        // needles taken from throughout the haystack, with a wildcard in the middle of each
+        let needles: Vec<_> = (0..count)
+            .map(|i| {
+                let start = haystack.len() / count * i;
+                let pattern: Vec<_> = haystack[start..start + 8]
+                    .iter()
+                    .enumerate()
+                    .map(|(j, byte)| {
+                        if j == 4 {
+                            "?".into()
+                        } else {
+                            format!("{byte:02X}")
+                        }
+                    })
+                    .collect();
+                DynamicNeedle::from_ida(&pattern.join(" ")).unwrap()
+            })
+            .collect();
+        let parameter = format!("{count} needles");
+
+        let set = NeedleSet::new(&needles);
+        group.bench_with_input(BenchmarkId::new("aob set", &parameter), &set, |b, set| {
+            b.iter(|| {
+                let count = set.find_iter(haystack).count();
+                hint::black_box(count);
+            });
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("aob each", &parameter),
+            &needles,
+            |b, needles| {
+                b.iter(|| {
+                    let count: usize = needles
+                        .iter()
+                        .map(|needle| needle.find_iter(haystack).count())
+                        .sum();
+                    hint::black_box(count);
+                });
+            },
+        );
+    }
+}
+
+criterion_group!(benches, bench_simple, bench_set);
 criterion_main!(benches);
@@ -0,0 +1,117 @@
+use crate::{
+    field::RawField,
+    pattern::{
+        PatternByte,
+        RawByteSet,
+    },
+};
+
+/// A single element of a parsed pattern, before it has been compiled into a needle.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Node {
+    /// Matches one byte in the haystack.
+    Byte(PatternByte),
+    /// Matches one byte in the haystack, if it is in the set.
+    Set(RawByteSet),
+    /// Skips anywhere from `min` to `max` bytes in the haystack, where a `max` of `None` is unbounded.
+    Jump { min: usize, max: Option<usize> },
+    /// Matches any one of the given sequences, preferring earlier sequences over later ones.
+    Alternation(Vec<Vec<Node>>),
+    /// Matches the given sequence, and records where it lies within the match.
+    Capture {
+        name: Option<String>,
+        nodes: Vec<Node>,
+    },
+    /// Matches nothing, and records the position of interest within the match.
+    Cursor,
+    /// Matches any byte, so long as it equals every other byte matched by a wildcard of the same name, and records it as a capture.
+    NamedWildcard(String),
+    /// Matches a number of bytes, if the number they encode is within the field's range.
+    Field(RawField),
+}
+
+impl Node {
+    /// The fewest number of bytes this node can match, or `None` if that, or the length of any sequence within it, overflows.
+    #[must_use]
+    pub(crate) fn min_len(&self) -> Option<usize> {
+        match self {
+            Self::Byte(_) | Self::Set(_) | Self::NamedWildcard(_) => Some(1),
+            Self::Field(field) => Some(field.len),
+            Self::Jump { min, .. } => Some(*min),
+            Self::Alternation(alternatives) => alternatives
+                .iter()
+                .map(|x| sequence_min_len(x))
+                .try_fold(None, |min: Option<usize>, len| {
+                    let len = len?;
+                    Some(Some(min.map_or(len, |min| min.min(len))))
+                })
+                .map(|min| min.unwrap_or(0)),
+            Self::Capture { nodes, .. } => sequence_min_len(nodes),
+            Self::Cursor => Some(0),
+        }
+    }
+
+    /// The greatest number of bytes this node can match, or `None` if it is unbounded.
+    #[must_use]
+    pub(crate) fn max_len(&self) -> Option<usize> {
+        match self {
+            Self::Byte(_) | Self::Set(_) | Self::NamedWildcard(_) => Some(1),
+            Self::Field(field) => Some(field.len),
+            Self::Jump { max, .. } => *max,
+            Self::Alternation(alternatives) => alternatives
+                .iter()
+                .map(|x| sequence_max_len(x))
+                .try_fold(0, |max, len| Some(max.max(len?))),
+            Self::Capture { nodes, .. } => sequence_max_len(nodes),
+            Self::Cursor => Some(0),
+        }
+    }
+
+    /// Whether this node always matches the same number of bytes.
+    #[must_use]
+    pub(crate) fn is_fixed_len(&self) -> bool {
+        match self {
+            Self::Byte(_)
+            | Self::Set(_)
+            | Self::Cursor
+            | Self::NamedWildcard(_)
+            | Self::Field(_) => true,
+            Self::Jump { min, max } => Some(*min) == *max,
+            Self::Alternation(_) => false,
+            Self::Capture { nodes, .. } => nodes.iter().all(Self::is_fixed_len),
+        }
+    }
+
+    /// The number of cursors within the node.
+    #[must_use]
+    pub(crate) fn cursors(&self) -> usize {
+        match self {
+            Self::Byte(_)
+            | Self::Set(_)
+            | Self::Jump { .. }
+            | Self::NamedWildcard(_)
+            | Self::Field(_) => 0,
+            Self::Alternation(alternatives) => {
+                alternatives.iter().flatten().map(Self::cursors).sum()
+            }
+            Self::Capture { nodes, .. } => nodes.iter().map(Self::cursors).sum(),
+            Self::Cursor => 1,
+        }
+    }
+}
+
+/// The fewest number of bytes a sequence of nodes can match, or `None` if it overflows.
+#[must_use]
+pub(crate) fn sequence_min_len(nodes: &[Node]) -> Option<usize> {
+    nodes
+        .iter()
+        .try_fold(0usize, |sum, node| sum.checked_add(node.min_len()?))
+}
+
+/// The greatest number of bytes a sequence of nodes can match, or `None` if it is unbounded.
+#[must_use]
+fn sequence_max_len(nodes: &[Node]) -> Option<usize> {
+    nodes
+        .iter()
+        .try_fold(0usize, |sum, node| sum.checked_add(node.max_len()?))
+}
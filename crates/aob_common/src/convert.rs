@@ -0,0 +1,473 @@
+use crate::{
+    ast::Node,
+    formatting,
+    parsing,
+    DynamicNeedle,
+    Error,
+};
+use std::fmt::{
+    self,
+    Display,
+    Formatter,
+};
+
+/// A [`Dialect`] is one of the signature formats a [`Signature`] can be read from or written to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Dialect {
+    /// Ida style patterns, as accepted by [`DynamicNeedle::from_ida`], e.g. `48 8B 05 ? ? ? ?`.
+    ///
+    /// Every [`Signature`] can be written as an Ida style pattern.
+    Ida,
+    /// x64dbg patterns, e.g. `48 8B 05 ?? ?? ?? ??`.
+    ///
+    /// Each byte is a pair of hexadecimals, either of which may be a `?`. Whitespace between bytes is optional, except between a `?` and a `?` of the next byte.
+    X64dbg,
+    /// Cheat Engine array of byte scans, e.g. `48 8B 05 * * ?? ??`.
+    ///
+    /// Each byte is a pair of hexadecimals, either of which may be a `?`, or a `?`, `*`, or `**` wildcard. Whitespace between bytes is optional, except where one wildcard follows another.
+    CheatEngine,
+    /// Code style patterns written as they appear in C or C++ source, e.g. `"\x48\x8B\x05\x00", "xxx?"`.
+    ///
+    /// The bytes are a quoted string of `\xHH` escapes or printable ASCII, and the mask is a quoted string accepted by [`DynamicNeedle::from_code_style`].
+    CodeStyle,
+    /// YARA hex strings, as accepted by [`DynamicNeedle::from_yara`], e.g. `{ 48 8B 05 [4] }`.
+    Yara,
+    /// C or Rust array literals, as accepted by [`DynamicNeedle::from_array_literal`], e.g. `{ 0x48, 0x8B, 0x05, ? }`.
+    ArrayLiteral,
+}
+
+impl Display for Dialect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ida => write!(f, "Ida"),
+            Self::X64dbg => write!(f, "x64dbg"),
+            Self::CheatEngine => write!(f, "Cheat Engine"),
+            Self::CodeStyle => write!(f, "code style"),
+            Self::Yara => write!(f, "YARA"),
+            Self::ArrayLiteral => write!(f, "array literal"),
+        }
+    }
+}
+
+/// A [`Feature`] of a [`Signature`] which some [`Dialect`]s can't represent.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Feature {
+    /// A byte where only one nibble must match, e.g. `4?`.
+    NibbleMask,
+    /// A set of bytes, or a negation, which can't be described by masking some bits of a byte.
+    Set,
+    /// A gap of a variable number of bytes.
+    Gap,
+    /// An alternation between several sequences.
+    Alternation,
+    /// A capture, which would be dropped.
+    Capture,
+    /// A cursor, which would be dropped.
+    Cursor,
+    /// A named wildcard, which must equal the other named wildcards of the same name.
+    NamedWildcard,
+    /// A number whose value must be within a range.
+    Range,
+    /// A sequence without any bytes, such as an empty signature or an empty alternative.
+    Empty,
+    /// A gap at the start or end of a sequence.
+    EdgeGap,
+    /// A gap with no upper bound, within an alternation.
+    UnboundedGap,
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NibbleMask => write!(f, "nibble mask"),
+            Self::Set => write!(f, "set of bytes"),
+            Self::Gap => write!(f, "variable length gap"),
+            Self::Alternation => write!(f, "alternation"),
+            Self::Capture => write!(f, "capture"),
+            Self::Cursor => write!(f, "cursor"),
+            Self::NamedWildcard => write!(f, "named wildcard"),
+            Self::Range => write!(f, "value range"),
+            Self::Empty => write!(f, "empty sequence"),
+            Self::EdgeGap => write!(f, "gap at the edge of a sequence"),
+            Self::UnboundedGap => write!(f, "unbounded gap within an alternation"),
+        }
+    }
+}
+
+/// Describes a [`Feature`] of a [`Signature`] which can't be represented in some [`Dialect`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Unrepresentable {
+    pub(crate) offset: usize,
+    pub(crate) feature: Feature,
+}
+
+impl Unrepresentable {
+    /// The offset of the feature from the start of a match.
+    ///
+    /// If the feature follows a gap of a variable number of bytes, this is the least offset it can have.
+    ///
+    /// ```
+    /// # use aob_common::{Dialect, Signature};
+    /// let signature = Signature::parse(Dialect::Ida, "48 [1-2] 8B 0?").unwrap();
+    /// let error = signature.write(Dialect::CodeStyle).unwrap_err();
+    /// let offsets: Vec<_> = error.unrepresentable().iter().map(|x| x.offset()).collect();
+    /// assert_eq!(offsets, [1, 3]);
+    /// ```
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The feature which can't be represented.
+    ///
+    /// ```
+    /// # use aob_common::{Dialect, Feature, Signature};
+    /// let signature = Signature::parse(Dialect::Ida, "48 8B 0?").unwrap();
+    /// let error = signature.write(Dialect::CodeStyle).unwrap_err();
+    /// assert_eq!(error.unrepresentable()[0].feature(), Feature::NibbleMask);
+    /// ```
+    #[must_use]
+    pub fn feature(&self) -> Feature {
+        self.feature
+    }
+}
+
+/// Describes why a [`Signature`] can't be written in a [`Dialect`] without losing information.
+#[derive(Clone, Debug)]
+pub struct ConversionError {
+    dialect: Dialect,
+    unrepresentable: Vec<Unrepresentable>,
+}
+
+impl ConversionError {
+    /// The dialect the signature couldn't be written in.
+    ///
+    /// ```
+    /// # use aob_common::{Dialect, Signature};
+    /// let signature = Signature::parse(Dialect::Ida, "48 (8B|89)").unwrap();
+    /// let error = signature.write(Dialect::X64dbg).unwrap_err();
+    /// assert_eq!(error.dialect(), Dialect::X64dbg);
+    /// ```
+    #[must_use]
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Every feature of the signature which can't be represented, ordered by where they begin in the signature.
+    ///
+    /// ```
+    /// # use aob_common::{Dialect, Feature, Signature};
+    /// let signature = Signature::parse(Dialect::Ida, "(disp: ? ?) 8B (E8|E9)").unwrap();
+    /// let error = signature.write(Dialect::X64dbg).unwrap_err();
+    /// let unrepresentable: Vec<_> = error
+    ///     .unrepresentable()
+    ///     .iter()
+    ///     .map(|x| (x.offset(), x.feature()))
+    ///     .collect();
+    /// assert_eq!(unrepresentable, [(0, Feature::Capture), (3, Feature::Set)]);
+    /// ```
+    #[must_use]
+    pub fn unrepresentable(&self) -> &[Unrepresentable] {
+        &self.unrepresentable
+    }
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "signature can't be written as {}:", self.dialect)?;
+        for (i, Unrepresentable { offset, feature }) in self.unrepresentable.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(f, "{separator} {feature} at offset {offset}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// A dialect-agnostic signature, for converting between each [`Dialect`].
+///
+/// ```
+/// # use aob_common::{Dialect, Signature};
+/// let signature = Signature::parse(Dialect::CodeStyle, r#""\x48\x8B\x05\x00", "xxx?""#).unwrap();
+/// assert_eq!(signature.write(Dialect::Ida).unwrap(), "48 8B 05 ?");
+/// assert_eq!(signature.write(Dialect::X64dbg).unwrap(), "48 8B 05 ??");
+/// assert_eq!(signature.write(Dialect::Yara).unwrap(), "{ 48 8B 05 ?? }");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Signature {
+    nodes: Vec<Node>,
+}
+
+impl Signature {
+    /// Parses a signature written in `dialect`.
+    ///
+    /// # Example
+    /// ```
+    /// # use aob_common::{Dialect, Signature};
+    /// let signature = Signature::parse(Dialect::CheatEngine, "48 8B 05 * * ?? ??").unwrap();
+    /// assert_eq!(signature.write(Dialect::CodeStyle).unwrap(), r#""\x48\x8B\x05\x00\x00\x00\x00", "xxx????""#);
+    ///
+    /// let error = Signature::parse(Dialect::X64dbg, "48 8B 0").unwrap_err();
+    /// assert_eq!(error.span(), 7..7);
+    /// ```
+    pub fn parse(dialect: Dialect, pattern: &str) -> Result<Self, Error<'_>> {
+        let nodes = match dialect {
+            Dialect::Ida => parsing::parse(parsing::ida_pattern(), pattern),
+            Dialect::X64dbg => parsing::parse(parsing::x64dbg_pattern(), pattern),
+            Dialect::CheatEngine => parsing::parse(parsing::cheat_engine_pattern(), pattern),
+            Dialect::CodeStyle => parsing::parse(parsing::code_style_pattern(), pattern),
+            Dialect::Yara => parsing::parse(parsing::yara_pattern(), pattern),
+            Dialect::ArrayLiteral => parsing::parse(parsing::array_literal(), pattern),
+        }?;
+        Ok(Self { nodes })
+    }
+
+    /// Writes the signature in `dialect`.
+    ///
+    /// Fails if the signature has any [`Feature`] that `dialect` can't represent, rather than writing a signature which matches differently.
+    ///
+    /// # Example
+    /// ```
+    /// # use aob_common::{Dialect, Feature, Signature};
+    /// let signature = Signature::parse(Dialect::Yara, "{ 48 8B ?5 [2-4] ( 90 | CC ) }").unwrap();
+    /// assert_eq!(signature.write(Dialect::Ida).unwrap(), "48 8B ?5 [2-4] (90|CC)");
+    ///
+    /// let error = signature.write(Dialect::CheatEngine).unwrap_err();
+    /// let unrepresentable: Vec<_> = error
+    ///     .unrepresentable()
+    ///     .iter()
+    ///     .map(|x| (x.offset(), x.feature()))
+    ///     .collect();
+    /// assert_eq!(unrepresentable, [(3, Feature::Gap), (5, Feature::Alternation)]);
+    /// ```
+    pub fn write(&self, dialect: Dialect) -> Result<String, ConversionError> {
+        match dialect {
+            Dialect::Ida => Ok(formatting::ida(&self.nodes)),
+            Dialect::X64dbg | Dialect::CheatEngine => formatting::hex_pairs(&self.nodes),
+            Dialect::CodeStyle => formatting::code_style(&self.nodes),
+            Dialect::Yara => formatting::yara(&self.nodes),
+            Dialect::ArrayLiteral => formatting::array_literal(&self.nodes),
+        }
+        .map_err(|unrepresentable| ConversionError {
+            dialect,
+            unrepresentable,
+        })
+    }
+
+    /// Constructs a [`DynamicNeedle`] which matches the signature.
+    ///
+    /// # Example
+    /// ```
+    /// # use aob_common::{Dialect, Needle as _, Signature};
+    /// let signature = Signature::parse(Dialect::X64dbg, "789?BC").unwrap();
+    /// let needle = signature.to_needle();
+    /// let haystack = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE];
+    /// assert_eq!(needle.find(&haystack).unwrap().range(), 3..6);
+    /// ```
+    #[must_use]
+    pub fn to_needle(&self) -> DynamicNeedle {
+        DynamicNeedle::from_nodes(&self.nodes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Dialect,
+        Feature,
+        Signature,
+    };
+    use crate::Needle as _;
+
+    #[test]
+    fn test_convert() {
+        let dialects = [
+            Dialect::Ida,
+            Dialect::X64dbg,
+            Dialect::CheatEngine,
+            Dialect::CodeStyle,
+            Dialect::Yara,
+            Dialect::ArrayLiteral,
+        ];
+        let haystack: Vec<u8> = (0..=u8::MAX).cycle().take(0x1000).collect();
+        for (dialect, pattern) in [
+            (Dialect::Ida, "10 ? 12 [1] 14"),
+            (Dialect::X64dbg, "10??12 ?? 14"),
+            (Dialect::CheatEngine, "10 * 12 ** 14"),
+            (Dialect::CodeStyle, r#" "\x10\x00\x12\x00\x14" , "x?x?x" "#),
+            (Dialect::Yara, "{ 10 ?? 12 [1] 14 }"),
+            (Dialect::ArrayLiteral, "[0x10, ?, 0x12, ??, 0x14,]"),
+        ] {
+            let signature = Signature::parse(dialect, pattern).unwrap();
+            assert_eq!(signature.to_needle().find_iter(&haystack).count(), 16);
+            for to in dialects {
+                let written = signature.write(to).unwrap();
+                let reparsed = Signature::parse(to, &written).unwrap();
+                assert_eq!(reparsed.write(to).unwrap(), written, "{dialect} -> {to}");
+                assert_eq!(
+                    reparsed.to_needle().find_iter(&haystack).count(),
+                    16,
+                    "{dialect} -> {to}"
+                );
+            }
+        }
+
+        let signature = Signature::parse(Dialect::Ida, "?F 48 ?").unwrap();
+        assert_eq!(signature.write(Dialect::X64dbg).unwrap(), "?F 48 ??");
+        assert_eq!(signature.write(Dialect::CheatEngine).unwrap(), "?F 48 ??");
+        assert_eq!(signature.write(Dialect::Yara).unwrap(), "{ ?F 48 ?? }");
+        let signature = Signature::parse(Dialect::CodeStyle, r#""", """#).unwrap();
+        assert_eq!(signature.write(Dialect::CodeStyle).unwrap(), r#""", """#);
+        assert!(signature.write(Dialect::Yara).is_err());
+        assert_eq!(signature.write(Dialect::ArrayLiteral).unwrap(), "{}");
+        let signature = Signature::parse(Dialect::CodeStyle, r#""MZ\x90", "xx?""#).unwrap();
+        assert_eq!(signature.write(Dialect::Ida).unwrap(), "4D 5A ?");
+
+        let signature = Signature::parse(Dialect::Ida, "11 [65535] 22").unwrap();
+        let written = signature.write(Dialect::X64dbg).unwrap();
+        assert_eq!(written.split(' ').count(), 65537);
+        assert!(Signature::parse(Dialect::Ida, "11 [100000000000] 22").is_err());
+    }
+
+    #[test]
+    fn test_yara_sets() {
+        let haystack: Vec<u8> = (0..=u8::MAX).collect();
+        for (pattern, expected, count) in [
+            ("!CC", "{ ~CC }", 255),
+            ("!4?", "{ ~4? }", 240),
+            ("(10-12)", "{ ( 10 | 11 | 12 ) }", 3),
+            (
+                "(E8|E9) [1-2] (EB ?|EC)",
+                "{ ( E8 | E9 ) [1-2] ( EB ?? | EC ) }",
+                2,
+            ),
+        ] {
+            let signature = Signature::parse(Dialect::Ida, pattern).unwrap();
+            let written = signature.write(Dialect::Yara).unwrap();
+            assert_eq!(written, expected);
+            let reparsed = Signature::parse(Dialect::Yara, &written).unwrap();
+            assert_eq!(
+                reparsed.to_needle().find_iter(&haystack).count(),
+                count,
+                "{pattern}"
+            );
+            assert_eq!(
+                signature.to_needle().find_iter(&haystack).count(),
+                count,
+                "{pattern}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unrepresentable() {
+        for (pattern, dialect, expected) in [
+            (
+                "48 8B 0? ?5",
+                Dialect::CodeStyle,
+                &[(2, Feature::NibbleMask), (3, Feature::NibbleMask)][..],
+            ),
+            ("48 8B 0? ?5", Dialect::X64dbg, &[]),
+            (
+                "48 8B 0?",
+                Dialect::ArrayLiteral,
+                &[(2, Feature::NibbleMask)],
+            ),
+            (
+                "(E8|E9) (00-0F) !CC",
+                Dialect::CheatEngine,
+                &[(0, Feature::Set), (2, Feature::Set)],
+            ),
+            (
+                "11 [1-3] 22 (33|44 55)",
+                Dialect::X64dbg,
+                &[(1, Feature::Gap), (3, Feature::Alternation)],
+            ),
+            ("11 [1-3] 22 (33|44 55)", Dialect::Yara, &[]),
+            (
+                "(a: 11 ^ ?<x>) ?<x>",
+                Dialect::Yara,
+                &[
+                    (0, Feature::Capture),
+                    (1, Feature::Cursor),
+                    (1, Feature::NamedWildcard),
+                    (2, Feature::NamedWildcard),
+                ],
+            ),
+            ("(11 ^|22)", Dialect::Yara, &[(1, Feature::Cursor)]),
+            ("", Dialect::Yara, &[(0, Feature::Empty)]),
+            (
+                "11 (00-7F) !(10|20) (10-1E)",
+                Dialect::Yara,
+                &[(1, Feature::Set), (2, Feature::Set)],
+            ),
+            (
+                "11 (^|22)",
+                Dialect::Yara,
+                &[(1, Feature::Cursor), (1, Feature::Empty)],
+            ),
+            (
+                "[1-2] 11 [3]",
+                Dialect::Yara,
+                &[(0, Feature::EdgeGap), (2, Feature::EdgeGap)],
+            ),
+            ("11 [2-]", Dialect::Yara, &[(1, Feature::EdgeGap)]),
+            (
+                "11 (22 [1-] 33|44 [0-1]) 55",
+                Dialect::Yara,
+                &[(2, Feature::UnboundedGap), (2, Feature::EdgeGap)],
+            ),
+            (
+                "11 u32(0x10..0x20) 22",
+                Dialect::X64dbg,
+                &[(1, Feature::Range)],
+            ),
+            (
+                "11 u32(0x10..0x20) 22",
+                Dialect::Yara,
+                &[(1, Feature::Range)],
+            ),
+        ] {
+            let signature = Signature::parse(Dialect::Ida, pattern).unwrap();
+            let result = signature.write(dialect);
+            if expected.is_empty() {
+                assert!(result.is_ok(), "{pattern} -> {dialect}");
+            } else {
+                let error = result.unwrap_err();
+                assert_eq!(error.dialect(), dialect);
+                let unrepresentable: Vec<_> = error
+                    .unrepresentable()
+                    .iter()
+                    .map(|x| (x.offset(), x.feature()))
+                    .collect();
+                assert_eq!(unrepresentable, expected, "{pattern} -> {dialect}");
+            }
+        }
+
+        let signature = Signature::parse(Dialect::Ida, "48 8B 0? [2-]").unwrap();
+        let error = signature.write(Dialect::CodeStyle).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "signature can't be written as code style: nibble mask at offset 2, variable length gap at offset 3"
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = Signature::parse(Dialect::CodeStyle, r#""\x48\x8B", "x?x""#).unwrap_err();
+        assert_eq!(error.span(), 15..16);
+        let error = Signature::parse(Dialect::CodeStyle, r#""\x48\x8B", "x.""#).unwrap_err();
+        assert_eq!(error.span(), 14..15);
+        assert!(Signature::parse(Dialect::CodeStyle, r#""\x4", "x""#).is_err());
+        assert!(Signature::parse(Dialect::X64dbg, "48 ? 8B").is_err());
+        assert!(Signature::parse(Dialect::CheatEngine, "48 *** 8B").is_err());
+        assert!(Signature::parse(Dialect::CheatEngine, "48 ?* 8B").is_err());
+        assert!(Signature::parse(Dialect::CheatEngine, "48 ** * ?8 8B").is_ok());
+        assert!(Signature::parse(Dialect::X64dbg, "48 ??? 8B").is_err());
+        assert!(Signature::parse(Dialect::X64dbg, "48 4??? 8B").is_err());
+        assert!(Signature::parse(Dialect::X64dbg, "48 ?? ?4 8B").is_ok());
+        assert!(Signature::parse(Dialect::CheatEngine, "48 [2] 8B").is_err());
+        assert!(Signature::parse(Dialect::Yara, "~??").is_err());
+    }
+}
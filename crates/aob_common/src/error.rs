@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     fmt::{
         self,
         Display,
@@ -9,6 +10,7 @@ use std::{
 
 /// A [`Reason`] gives more context about why parsing failed.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum Reason {
     /// Encountered an unexpected input in the stream.
     Unexpected,
@@ -16,6 +18,29 @@ pub enum Reason {
     Unclosed,
     /// The given character is not a valid hexdigit.
     InvalidHexdigit(char),
+    /// The given character is not a valid mask character.
+    InvalidMaskCharacter(char),
+    /// The input has a different length than what it is paired with.
+    LengthMismatch {
+        /// The length of what the input is paired with.
+        expected: usize,
+        /// The length of the input.
+        found: usize,
+    },
+    /// The given number is too large to be represented.
+    Overflow,
+    /// The lower bound of a range is greater than its upper bound.
+    InvertedRange,
+    /// The set of bytes is empty, so it can never match.
+    EmptySet,
+    /// The pattern contains more than one cursor.
+    DuplicateCursor,
+    /// The token is repeated zero times.
+    EmptyRepetition,
+    /// The parentheses are nested too deeply.
+    NestedTooDeeply,
+    /// The name is already given to another capture.
+    DuplicateName,
 }
 
 impl Display for Reason {
@@ -24,6 +49,17 @@ impl Display for Reason {
             Self::Unexpected => write!(f, "unexpected input"),
             Self::Unclosed => write!(f, "unclosed delimiter"),
             Self::InvalidHexdigit(c) => write!(f, "'{c}' is not a hexdigit"),
+            Self::InvalidMaskCharacter(c) => write!(f, "'{c}' is not a mask character"),
+            Self::LengthMismatch { expected, found } => {
+                write!(f, "expected a length of {expected}, but found {found}")
+            }
+            Self::Overflow => write!(f, "number is too large"),
+            Self::InvertedRange => write!(f, "lower bound is greater than the upper bound"),
+            Self::EmptySet => write!(f, "set of bytes can never match"),
+            Self::DuplicateCursor => write!(f, "pattern already has a cursor"),
+            Self::EmptyRepetition => write!(f, "token must be repeated at least once"),
+            Self::NestedTooDeeply => write!(f, "parentheses are nested too deeply"),
+            Self::DuplicateName => write!(f, "name is already given to another capture"),
         }
     }
 }
@@ -41,6 +77,76 @@ impl SimpleError {
             reason: Reason::InvalidHexdigit(found),
         }
     }
+
+    pub(crate) fn invalid_mask_character(span: Range<usize>, found: char) -> Self {
+        Self {
+            span,
+            reason: Reason::InvalidMaskCharacter(found),
+        }
+    }
+
+    pub(crate) fn unexpected(span: Range<usize>) -> Self {
+        Self {
+            span,
+            reason: Reason::Unexpected,
+        }
+    }
+
+    pub(crate) fn overflow(span: Range<usize>) -> Self {
+        Self {
+            span,
+            reason: Reason::Overflow,
+        }
+    }
+
+    pub(crate) fn inverted_range(span: Range<usize>) -> Self {
+        Self {
+            span,
+            reason: Reason::InvertedRange,
+        }
+    }
+
+    pub(crate) fn empty_set(span: Range<usize>) -> Self {
+        Self {
+            span,
+            reason: Reason::EmptySet,
+        }
+    }
+
+    pub(crate) fn duplicate_cursor(span: Range<usize>) -> Self {
+        Self {
+            span,
+            reason: Reason::DuplicateCursor,
+        }
+    }
+
+    pub(crate) fn empty_repetition(span: Range<usize>) -> Self {
+        Self {
+            span,
+            reason: Reason::EmptyRepetition,
+        }
+    }
+
+    pub(crate) fn nested_too_deeply(span: Range<usize>) -> Self {
+        Self {
+            span,
+            reason: Reason::NestedTooDeeply,
+        }
+    }
+
+    pub(crate) fn duplicate_name(span: Range<usize>) -> Self {
+        Self {
+            span,
+            reason: Reason::DuplicateName,
+        }
+    }
+
+    pub(crate) fn length_mismatch(span: Range<usize>, expected: usize, found: usize) -> Self {
+        Self {
+            span,
+            reason: Reason::LengthMismatch { expected, found },
+        }
+    }
 }
 
 impl chumsky::Error<char> for SimpleError {
@@ -83,11 +189,29 @@ impl chumsky::Error<char> for SimpleError {
 /// Describes errors encountered when parsing custom pattern syntax.
 #[derive(Clone, Debug)]
 pub struct Error<'a> {
-    pub(crate) source: &'a str,
+    pub(crate) source: Cow<'a, str>,
     pub(crate) inner: SimpleError,
 }
 
 impl<'a> Error<'a> {
+    /// Copies the source of the pattern, so that the error no longer borrows it.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Error};
+    /// let error: Error<'static> = {
+    ///     let pattern = String::from("12 3_ 56");
+    ///     DynamicNeedle::from_ida(&pattern).unwrap_err().into_owned()
+    /// };
+    /// assert_eq!(error.span(), 4..5);
+    /// ```
+    #[must_use]
+    pub fn into_owned(self) -> Error<'static> {
+        Error {
+            source: Cow::Owned(self.source.into_owned()),
+            inner: self.inner,
+        }
+    }
+
     /// The span over which the error was encountered.
     ///
     /// ```
@@ -117,7 +241,8 @@ impl Display for Error<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let start = self.inner.span.start;
         let end = self.inner.span.end;
-        let span = &self.source[start..end];
+        // spans count characters rather than bytes
+        let span: String = self.source.chars().skip(start).take(end - start).collect();
         write!(
             f,
             "error while parsing token \"{span}\" in range [{start}, {end})",
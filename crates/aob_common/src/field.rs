@@ -0,0 +1,289 @@
+use crate::pattern::PatternByte;
+
+/// How the bytes of a [`RawField`] are interpreted as a number.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RawScalar {
+    Unsigned,
+    Signed,
+    Float,
+}
+
+/// A number within a match, whose value must lie within an inclusive range.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RawField {
+    pub scalar: RawScalar,
+    /// The size of the number in bytes, which is 1, 2, 4, or 8.
+    pub len: usize,
+    pub big_endian: bool,
+    /// The bits of the least value the number may have.
+    pub min: u64,
+    /// The bits of the greatest value the number may have.
+    pub max: u64,
+}
+
+impl RawField {
+    /// Every bit of a number of `len` bytes.
+    #[must_use]
+    pub(crate) fn all_bits(len: usize) -> u64 {
+        u64::MAX >> (64 - len * 8)
+    }
+
+    /// The bits of the least value a `scalar` of `len` bytes can have.
+    #[must_use]
+    pub(crate) fn lowest(scalar: RawScalar, len: usize) -> u64 {
+        match scalar {
+            RawScalar::Unsigned => 0,
+            RawScalar::Signed => 1 << (len * 8 - 1),
+            RawScalar::Float if len == 4 => f32::NEG_INFINITY.to_bits().into(),
+            RawScalar::Float => f64::NEG_INFINITY.to_bits(),
+        }
+    }
+
+    /// The bits of the greatest value a `scalar` of `len` bytes can have.
+    #[must_use]
+    pub(crate) fn highest(scalar: RawScalar, len: usize) -> u64 {
+        match scalar {
+            RawScalar::Unsigned => Self::all_bits(len),
+            RawScalar::Signed => Self::all_bits(len) >> 1,
+            RawScalar::Float if len == 4 => f32::INFINITY.to_bits().into(),
+            RawScalar::Float => f64::INFINITY.to_bits(),
+        }
+    }
+
+    /// The bits of the greatest value of a `scalar` of `len` bytes which is less than `bits`, if there is one.
+    #[must_use]
+    pub(crate) fn previous(scalar: RawScalar, len: usize, bits: u64) -> Option<u64> {
+        if bits == Self::lowest(scalar, len) {
+            return None;
+        }
+        match scalar {
+            RawScalar::Unsigned | RawScalar::Signed => {
+                Some(bits.wrapping_sub(1) & Self::all_bits(len))
+            }
+            RawScalar::Float => {
+                // positive floats order the same as their bits, and negative floats the opposite way
+                let sign = 1 << (len * 8 - 1);
+                Some(if bits & !sign == 0 {
+                    sign | 1
+                } else if bits & sign == 0 {
+                    bits - 1
+                } else {
+                    bits + 1
+                })
+            }
+        }
+    }
+
+    /// Whether the range has no values at all.
+    #[must_use]
+    pub(crate) fn is_empty(self) -> bool {
+        !self.contains_bits(self.min)
+    }
+
+    /// Whether the number at the start of `bytes` lies within the range, where `bytes` has at least [`RawField::len`] bytes.
+    #[must_use]
+    pub(crate) fn contains(self, bytes: &[u8]) -> bool {
+        let mut buffer = [0; 8];
+        buffer[..self.len].copy_from_slice(&bytes[..self.len]);
+        if self.big_endian {
+            buffer[..self.len].reverse();
+        }
+        self.contains_bits(u64::from_le_bytes(buffer))
+    }
+
+    #[must_use]
+    fn contains_bits(self, bits: u64) -> bool {
+        match self.scalar {
+            RawScalar::Unsigned => (self.min..=self.max).contains(&bits),
+            RawScalar::Signed => {
+                (self.signed(self.min)..=self.signed(self.max)).contains(&self.signed(bits))
+            }
+            RawScalar::Float => {
+                (self.float(self.min)..=self.float(self.max)).contains(&self.float(bits))
+            }
+        }
+    }
+
+    /// The value of `bits`, sign extended from the size of the field.
+    #[must_use]
+    pub(crate) fn signed(self, bits: u64) -> i64 {
+        let shift = 64 - self.len * 8;
+        i64::from_ne_bytes((bits << shift).to_ne_bytes()) >> shift
+    }
+
+    /// The value of `bits`, widened from the size of the field.
+    #[must_use]
+    pub(crate) fn float(self, bits: u64) -> f64 {
+        if self.len == 4 {
+            Self::float32(bits).into()
+        } else {
+            f64::from_bits(bits)
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn float32(bits: u64) -> f32 {
+        f32::from_bits(u32::try_from(bits).expect("bits should fit within 4 bytes"))
+    }
+
+    /// Approximates the range as the bits shared by every value within it.
+    #[must_use]
+    pub(crate) fn approximate(self) -> Vec<PatternByte> {
+        // every value between the bounds also has bits between their bits, unless the bounds have different signs,
+        // in which case their highest bits already differ
+        let all = Self::all_bits(self.len);
+        let differing = (self.min ^ self.max) & all;
+        let mut mask = all & !u64::MAX.checked_shr(differing.leading_zeros()).unwrap_or(0);
+        if self.scalar == RawScalar::Float && self.contains_bits(0) {
+            // negative zero lies within any range that zero does, but has the opposite sign
+            mask &= !(1 << (self.len * 8 - 1));
+        }
+        to_pattern_bytes(self.min & mask, mask, self.len, self.big_endian)
+    }
+}
+
+/// Splits the `len` lowest bytes of `word` and `mask` into pattern bytes, in little or big endian order.
+#[must_use]
+pub(crate) fn to_pattern_bytes(
+    word: u64,
+    mask: u64,
+    len: usize,
+    big_endian: bool,
+) -> Vec<PatternByte> {
+    let mut bytes: Vec<_> = word.to_le_bytes()[..len]
+        .iter()
+        .zip(&mask.to_le_bytes()[..len])
+        .map(|(&word, &mask)| PatternByte { word, mask })
+        .collect();
+    if big_endian {
+        bytes.reverse();
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        RawField,
+        RawScalar,
+    };
+    use crate::pattern::PatternByte;
+
+    #[test]
+    fn test_contains() {
+        let field = RawField {
+            scalar: RawScalar::Unsigned,
+            len: 4,
+            big_endian: false,
+            min: 0x1000,
+            max: 0x1FFF,
+        };
+        assert!(field.contains(&[0x00, 0x10, 0x00, 0x00]));
+        assert!(field.contains(&[0xFF, 0x1F, 0x00, 0x00, 0xAA]));
+        assert!(!field.contains(&[0x00, 0x20, 0x00, 0x00]));
+        assert!(!field.contains(&[0xFF, 0x0F, 0x00, 0x00]));
+
+        let field = RawField {
+            scalar: RawScalar::Signed,
+            len: 2,
+            big_endian: true,
+            min: 0xFFFE,
+            max: 0x0003,
+        };
+        assert!(field.contains(&[0xFF, 0xFE]));
+        assert!(field.contains(&[0x00, 0x03]));
+        assert!(!field.contains(&[0xFF, 0xFD]));
+        assert!(!field.contains(&[0x80, 0x00]));
+
+        let field = RawField {
+            scalar: RawScalar::Float,
+            len: 4,
+            big_endian: false,
+            min: (-1.5f32).to_bits().into(),
+            max: 2.0f32.to_bits().into(),
+        };
+        assert!(field.contains(&0.25f32.to_le_bytes()));
+        assert!(field.contains(&(-1.5f32).to_le_bytes()));
+        assert!(!field.contains(&2.5f32.to_le_bytes()));
+        assert!(!field.contains(&f32::NAN.to_le_bytes()));
+    }
+
+    #[test]
+    fn test_approximate() {
+        let field = RawField {
+            scalar: RawScalar::Unsigned,
+            len: 4,
+            big_endian: true,
+            min: 0x1000,
+            max: 0x1FFF,
+        };
+        assert_eq!(
+            field.approximate(),
+            [
+                PatternByte::exact(0x00),
+                PatternByte::exact(0x00),
+                PatternByte {
+                    word: 0x10,
+                    mask: 0xF0
+                },
+                PatternByte::WILDCARD,
+            ]
+        );
+
+        // bounds with different signs share no bits
+        let field = RawField {
+            scalar: RawScalar::Signed,
+            len: 2,
+            big_endian: false,
+            min: 0xFFFE,
+            max: 0x0003,
+        };
+        assert_eq!(field.approximate(), [PatternByte::WILDCARD; 2]);
+
+        // ranges which include zero also include negative zero
+        let field = RawField {
+            scalar: RawScalar::Float,
+            len: 4,
+            big_endian: false,
+            min: 0.0f32.to_bits().into(),
+            max: 1.0f32.to_bits().into(),
+        };
+        let approximation = field.approximate();
+        assert_eq!(
+            approximation[3],
+            PatternByte {
+                word: 0,
+                mask: 0x40
+            }
+        );
+        assert!(approximation
+            .iter()
+            .zip((-0.0f32).to_le_bytes())
+            .all(|(byte, value)| byte.accepts(value)));
+        assert!(field.contains(&(-0.0f32).to_le_bytes()));
+
+        assert_eq!(RawField::previous(RawScalar::Signed, 1, 0x00), Some(0xFF));
+        assert_eq!(RawField::previous(RawScalar::Signed, 1, 0x80), None);
+        assert_eq!(RawField::previous(RawScalar::Unsigned, 1, 0x00), None);
+        for (value, expected) in [
+            (1.5f32, 0x3FBF_FFFF),
+            (-1.5, 0xBFC0_0001),
+            (0.0, 0x8000_0001),
+            (-0.0, 0x8000_0001),
+            (f32::INFINITY, f32::MAX.to_bits()),
+        ] {
+            let previous = RawField::previous(RawScalar::Float, 4, value.to_bits().into());
+            assert_eq!(previous, Some(expected.into()), "{value}");
+        }
+        assert_eq!(
+            RawField::previous(RawScalar::Float, 8, 1.0f64.to_bits()),
+            Some(0x3FEF_FFFF_FFFF_FFFF)
+        );
+        assert_eq!(
+            RawField::previous(RawScalar::Float, 4, f32::NEG_INFINITY.to_bits().into()),
+            None
+        );
+    }
+}
@@ -0,0 +1,474 @@
+use crate::{
+    ast::Node,
+    convert::{
+        Feature,
+        Unrepresentable,
+    },
+    field::{
+        RawField,
+        RawScalar,
+    },
+    pattern::{
+        PatternByte,
+        RawByteSet,
+    },
+};
+use std::fmt::Write as _;
+
+/// Writes `nodes` as an Ida style pattern, which parses back into an equivalent needle.
+#[must_use]
+pub(crate) fn ida(nodes: &[Node]) -> String {
+    let mut result = String::new();
+    write_sequence(&mut result, nodes);
+    result
+}
+
+fn write_sequence(out: &mut String, nodes: &[Node]) {
+    for (i, node) in nodes.iter().enumerate() {
+        if i != 0 {
+            out.push(' ');
+        }
+        write_node(out, node);
+    }
+}
+
+fn write_node(out: &mut String, node: &Node) {
+    match node {
+        Node::Byte(byte) => write_byte(out, *byte),
+        Node::Set(set) => write_set(out, *set),
+        Node::Jump { min, max } => match max {
+            Some(max) if max == min => write!(out, "[{min}]").unwrap(),
+            Some(max) => write!(out, "[{min}-{max}]").unwrap(),
+            None => write!(out, "[{min}-]").unwrap(),
+        },
+        Node::Alternation(alternatives) => {
+            if let [alternative] = &alternatives[..] {
+                write_sequence(out, alternative);
+            } else {
+                out.push('(');
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i != 0 {
+                        out.push('|');
+                    }
+                    write_sequence(out, alternative);
+                }
+                out.push(')');
+            }
+        }
+        Node::Capture { name, nodes } => {
+            out.push('(');
+            if let Some(name) = name {
+                write!(out, "{name}: ").unwrap();
+            }
+            write_sequence(out, nodes);
+            out.push(')');
+        }
+        Node::Cursor => out.push('^'),
+        Node::NamedWildcard(name) => write!(out, "?<{name}>").unwrap(),
+        Node::Field(field) => write_field(out, *field),
+    }
+}
+
+/// Writes `field` as a typed range, e.g. `u32le(0x1000..=0x1FFF)`, leaving out any bound which is the limit of its type.
+fn write_field(out: &mut String, field: RawField) {
+    let scalar = match field.scalar {
+        RawScalar::Unsigned => 'u',
+        RawScalar::Signed => 'i',
+        RawScalar::Float => 'f',
+    };
+    let endian = match (field.len, field.big_endian) {
+        (1, _) => "",
+        (_, false) => "le",
+        (_, true) => "be",
+    };
+    write!(out, "{scalar}{}{endian}(", field.len * 8).unwrap();
+    let write_bound = |out: &mut String, bits| match field.scalar {
+        RawScalar::Unsigned => write!(out, "0x{bits:X}").unwrap(),
+        RawScalar::Signed => write!(out, "{}", field.signed(bits)).unwrap(),
+        RawScalar::Float if field.len == 4 => write!(out, "{}", RawField::float32(bits)).unwrap(),
+        RawScalar::Float => write!(out, "{}", field.float(bits)).unwrap(),
+    };
+    if field.min != RawField::lowest(field.scalar, field.len) {
+        write_bound(out, field.min);
+    }
+    out.push_str("..");
+    if field.max != RawField::highest(field.scalar, field.len) {
+        out.push('=');
+        write_bound(out, field.max);
+    }
+    out.push(')');
+}
+
+/// Writes `byte` as a `byte` or `wildcard` if it can be, or else as a `set`.
+fn write_byte(out: &mut String, byte: PatternByte) {
+    if !try_write_byte(out, byte, "?") {
+        write_set(out, RawByteSet::from(byte));
+    }
+}
+
+/// Writes `byte` as a pair of hexadecimals or `wildcard`, if its mask only covers whole nibbles.
+fn try_write_byte(out: &mut String, byte: PatternByte, wildcard: &str) -> bool {
+    let PatternByte { word, mask } = byte;
+    match mask {
+        0xFF => write!(out, "{word:02X}").unwrap(),
+        0xF0 => write!(out, "{:X}?", word >> 4).unwrap(),
+        0x0F => write!(out, "?{:X}", word & 0x0F).unwrap(),
+        0x00 => out.push_str(wildcard),
+        _ => return false,
+    }
+    true
+}
+
+/// Whether `mask` only covers whole nibbles, and isn't a `wildcard`.
+#[must_use]
+fn is_nibble_mask(mask: u8) -> bool {
+    matches!(mask, 0xFF | 0xF0 | 0x0F)
+}
+
+/// Writes `set` using whichever of a `byte`, `set`, or `negation` is shortest.
+fn write_set(out: &mut String, set: RawByteSet) {
+    let (approximation, exact) = set.approximate();
+    if exact && try_write_byte(out, approximation, "?") {
+        return;
+    }
+
+    let complement = set.complement();
+    let (approximation, exact) = complement.approximate();
+    if exact && is_nibble_mask(approximation.mask) {
+        out.push('!');
+        try_write_byte(out, approximation, "?");
+    } else if ranges(complement).count() < ranges(set).count() {
+        out.push('!');
+        write_ranges(out, complement);
+    } else {
+        write_ranges(out, set);
+    }
+}
+
+/// Writes `set` as a `set` of its ranges, e.g. `(00-1F|7F)`.
+fn write_ranges(out: &mut String, set: RawByteSet) {
+    out.push('(');
+    for (i, (first, last)) in ranges(set).enumerate() {
+        if i != 0 {
+            out.push('|');
+        }
+        match last - first {
+            0 => write!(out, "{first:02X}").unwrap(),
+            1 => write!(out, "{first:02X}|{last:02X}").unwrap(),
+            _ => write!(out, "{first:02X}-{last:02X}").unwrap(),
+        }
+    }
+    out.push(')');
+}
+
+/// The inclusive ranges of consecutive bytes in `set`, in ascending order.
+fn ranges(set: RawByteSet) -> impl Iterator<Item = (u8, u8)> {
+    let mut bytes = (0..=u8::MAX).filter(move |&x| set.contains(x)).peekable();
+    std::iter::from_fn(move || {
+        let first = bytes.next()?;
+        let mut last = first;
+        while let Some(next) = bytes.next_if(|&x| Some(x) == last.checked_add(1)) {
+            last = next;
+        }
+        Some((first, last))
+    })
+}
+
+/// Writes `nodes` as pairs of hexadecimals, as used by x64dbg and Cheat Engine.
+pub(crate) fn hex_pairs(nodes: &[Node]) -> Result<String, Vec<Unrepresentable>> {
+    let bytes = flatten(nodes, true)?;
+    let mut result = String::new();
+    for (i, &byte) in bytes.iter().enumerate() {
+        if i != 0 {
+            result.push(' ');
+        }
+        try_write_byte(&mut result, byte, "??");
+    }
+    Ok(result)
+}
+
+/// Writes `nodes` as a quoted string of bytes and a quoted mask, separated by a comma.
+pub(crate) fn code_style(nodes: &[Node]) -> Result<String, Vec<Unrepresentable>> {
+    let bytes = flatten(nodes, false)?;
+    let mut result = String::from('"');
+    for byte in &bytes {
+        write!(result, "\\x{:02X}", byte.word & byte.mask).unwrap();
+    }
+    result.push_str("\", \"");
+    for byte in &bytes {
+        result.push(if byte.mask == 0 { '?' } else { 'x' });
+    }
+    result.push('"');
+    Ok(result)
+}
+
+/// Writes `nodes` as a comma separated sequence of `0x` prefixed bytes or `?` wildcards, enclosed in braces.
+pub(crate) fn array_literal(nodes: &[Node]) -> Result<String, Vec<Unrepresentable>> {
+    let elements: Vec<_> = flatten(nodes, false)?
+        .into_iter()
+        .map(|byte| {
+            if byte.mask == 0 {
+                "?".to_owned()
+            } else {
+                format!("0x{:02X}", byte.word)
+            }
+        })
+        .collect();
+    if elements.is_empty() {
+        Ok("{}".to_owned())
+    } else {
+        Ok(format!("{{ {} }}", elements.join(", ")))
+    }
+}
+
+/// Flattens `nodes` into a fixed sequence of bytes, where each byte must match exactly, match a single nibble if `nibbles` is set, or be a `wildcard`.
+fn flatten(nodes: &[Node], nibbles: bool) -> Result<Vec<PatternByte>, Vec<Unrepresentable>> {
+    fn visit(
+        nodes: &[Node],
+        nibbles: bool,
+        bytes: &mut Vec<PatternByte>,
+        errors: &mut Vec<Unrepresentable>,
+    ) {
+        for node in nodes {
+            // anything unrepresentable is padded out to its shortest length, to keep later offsets meaningful
+            let offset = bytes.len();
+            let mut unrepresentable = |feature| errors.push(Unrepresentable { offset, feature });
+            match node {
+                Node::Byte(_) | Node::Set(_) => {
+                    let set = match node {
+                        Node::Byte(byte) => RawByteSet::from(*byte),
+                        Node::Set(set) => *set,
+                        _ => unreachable!(),
+                    };
+                    let (approximation, exact) = set.approximate();
+                    if !exact || !(approximation.mask == 0 || is_nibble_mask(approximation.mask)) {
+                        unrepresentable(Feature::Set);
+                    } else if !(nibbles || matches!(approximation.mask, 0x00 | 0xFF)) {
+                        unrepresentable(Feature::NibbleMask);
+                    }
+                    bytes.push(approximation);
+                }
+                Node::Jump { min, max } => {
+                    if Some(*min) != *max {
+                        unrepresentable(Feature::Gap);
+                    }
+                    bytes.extend(std::iter::repeat_n(PatternByte::WILDCARD, *min));
+                }
+                Node::Alternation(alternatives) => {
+                    if let [alternative] = &alternatives[..] {
+                        visit(alternative, nibbles, bytes, errors);
+                    } else {
+                        unrepresentable(Feature::Alternation);
+                        let len = node
+                            .min_len()
+                            .expect("the length of a parsed pattern should fit in a usize");
+                        bytes.extend(std::iter::repeat_n(PatternByte::WILDCARD, len));
+                    }
+                }
+                Node::Capture { nodes, .. } => {
+                    unrepresentable(Feature::Capture);
+                    visit(nodes, nibbles, bytes, errors);
+                }
+                Node::Cursor => unrepresentable(Feature::Cursor),
+                Node::NamedWildcard(_) => {
+                    unrepresentable(Feature::NamedWildcard);
+                    bytes.push(PatternByte::WILDCARD);
+                }
+                Node::Field(field) => {
+                    unrepresentable(Feature::Range);
+                    bytes.extend(std::iter::repeat_n(PatternByte::WILDCARD, field.len));
+                }
+            }
+        }
+    }
+
+    let mut bytes = Vec::new();
+    let mut errors = Vec::new();
+    visit(nodes, nibbles, &mut bytes, &mut errors);
+    if errors.is_empty() {
+        Ok(bytes)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Writes `nodes` as a YARA hex string.
+pub(crate) fn yara(nodes: &[Node]) -> Result<String, Vec<Unrepresentable>> {
+    let mut errors = Vec::new();
+    let tokens = yara_tokens(nodes, 0, false, &mut errors);
+    if errors.is_empty() {
+        Ok(format!("{{ {} }}", tokens.join(" ")))
+    } else {
+        errors.sort_by_key(|error| error.offset);
+        Err(errors)
+    }
+}
+
+/// Writes `nodes` as a sequence of YARA tokens, which YARA requires to be non-empty and to neither begin nor end with a jump.
+fn yara_tokens(
+    nodes: &[Node],
+    offset: usize,
+    in_alternation: bool,
+    errors: &mut Vec<Unrepresentable>,
+) -> Vec<String> {
+    let tokens = yara_sequence(nodes, offset, in_alternation, errors);
+    if tokens.is_empty() {
+        errors.push(Unrepresentable {
+            offset,
+            feature: Feature::Empty,
+        });
+    }
+    let edges = [tokens.first(), tokens.get(1..).and_then(<[_]>::last)];
+    for (offset, token) in edges.into_iter().flatten() {
+        if token.starts_with('[') {
+            errors.push(Unrepresentable {
+                offset: *offset,
+                feature: Feature::EdgeGap,
+            });
+        }
+    }
+    tokens.into_iter().map(|(_, token)| token).collect()
+}
+
+/// Writes each of `nodes` as YARA tokens, along with their offsets, where the first begins `offset` bytes from the start of a match.
+fn yara_sequence(
+    nodes: &[Node],
+    mut offset: usize,
+    in_alternation: bool,
+    errors: &mut Vec<Unrepresentable>,
+) -> Vec<(usize, String)> {
+    let mut tokens = Vec::new();
+    for node in nodes {
+        let mut unrepresentable = |feature| errors.push(Unrepresentable { offset, feature });
+        match node {
+            Node::Byte(_) | Node::Set(_) => {
+                let set = match node {
+                    Node::Byte(byte) => RawByteSet::from(*byte),
+                    Node::Set(set) => *set,
+                    _ => unreachable!(),
+                };
+                let token = yara_set(set).unwrap_or_else(|| {
+                    unrepresentable(Feature::Set);
+                    "??".into()
+                });
+                tokens.push((offset, token));
+            }
+            Node::Jump { min, max } => tokens.push((
+                offset,
+                match max {
+                    Some(max) if max == min => format!("[{min}]"),
+                    Some(max) => format!("[{min}-{max}]"),
+                    None => {
+                        if in_alternation {
+                            unrepresentable(Feature::UnboundedGap);
+                        }
+                        format!("[{min}-]")
+                    }
+                },
+            )),
+            Node::Alternation(alternatives) => {
+                if let [alternative] = &alternatives[..] {
+                    tokens.extend(yara_sequence(alternative, offset, in_alternation, errors));
+                } else {
+                    let alternatives: Vec<_> = alternatives
+                        .iter()
+                        .map(|alternative| yara_tokens(alternative, offset, true, errors).join(" "))
+                        .collect();
+                    tokens.push((offset, format!("( {} )", alternatives.join(" | "))));
+                }
+            }
+            Node::Capture { nodes, .. } => {
+                unrepresentable(Feature::Capture);
+                tokens.extend(yara_sequence(nodes, offset, in_alternation, errors));
+            }
+            Node::Cursor => unrepresentable(Feature::Cursor),
+            Node::NamedWildcard(_) => {
+                unrepresentable(Feature::NamedWildcard);
+                tokens.push((offset, "??".into()));
+            }
+            Node::Field(field) => {
+                unrepresentable(Feature::Range);
+                tokens.extend((offset..offset + field.len).map(|offset| (offset, "??".into())));
+            }
+        }
+        offset += node
+            .min_len()
+            .expect("the length of a parsed pattern should fit in a usize");
+    }
+    tokens
+}
+
+/// The most bytes a set may have to be written as a YARA alternation, beyond which the alternation would grow unreasonably large.
+const MAX_YARA_ALTERNATIVES: usize = 16;
+
+/// Writes `set` as a YARA `byte` or negation if it can be, or else as an alternation of each byte in it.
+///
+/// Yields `None` if the set has too many bytes to be written as an alternation.
+#[must_use]
+fn yara_set(set: RawByteSet) -> Option<String> {
+    let mut result = String::new();
+    let (approximation, exact) = set.approximate();
+    if exact && try_write_byte(&mut result, approximation, "??") {
+        return Some(result);
+    }
+
+    let (approximation, exact) = set.complement().approximate();
+    if exact && is_nibble_mask(approximation.mask) {
+        result.push('~');
+        try_write_byte(&mut result, approximation, "??");
+    } else if set.len() <= MAX_YARA_ALTERNATIVES {
+        let bytes: Vec<_> = set.iter().map(|byte| format!("{byte:02X}")).collect();
+        write!(result, "( {} )", bytes.join(" | ")).unwrap();
+    } else {
+        return None;
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsing;
+
+    #[test]
+    fn test_ida() {
+        for (pattern, expected) in [
+            ("48 8b 05 ?? ? ?", "48 8B 05 ? ? ?"),
+            ("4? ?f", "4? ?F"),
+            (
+                "11 [2] 22 [1-3] 33 [4-] 44 [-]",
+                "11 [2] 22 [1-3] 33 [4-] 44 [0-]",
+            ),
+            ("(E8|E9) (00-1F|7F) (CC|CC)", "(E8|E9) (00-1F|7F) CC"),
+            ("(40-4F) (00-FF)", "4? ?"),
+            ("!CC !(E8|E9) !0?", "!CC !(E8|E9) !0?"),
+            ("!(10|20|30) (00-0F|11-FF)", "!(10|20|30) !10"),
+            ("( disp : ? ? ) (AB: CD) (EF)", "(disp: ? ?) (AB: CD) (EF)"),
+            ("AA ^ BB ?<x> ?<x>{2}", "AA ^ BB ?<x> ?<x> ?<x>"),
+            ("CC{3} ; comment", "CC CC CC"),
+            ("(11 22 | 33) (44|55 ?)", "(11 22|33) (44|55 ?)"),
+            (
+                "u32(0x1000..0x2000) i16be(-2..=3) u8(..)",
+                "u32le(0x1000..=0x1FFF) i16be(-2..=3) u8(..)",
+            ),
+            (
+                "f32(..=1.5) f64be(0.1..) u64(0x10..)",
+                "f32le(..=1.5) f64be(0.1..) u64le(0x10..)",
+            ),
+            ("", ""),
+        ] {
+            let nodes = parsing::parse(parsing::ida_pattern(), pattern).unwrap();
+            let formatted = super::ida(&nodes);
+            assert_eq!(formatted, expected, "{pattern}");
+            let reparsed = parsing::parse(parsing::ida_pattern(), &formatted).unwrap();
+            assert_eq!(super::ida(&reparsed), expected, "{pattern}");
+        }
+
+        for (pattern, expected) in [
+            ("{ 4D 5A [2-4] ( 90 | CC ) ?? }", "4D 5A [2-4] (90|CC) ?"),
+            ("( 11 22 | 33 ) ( 44 )", "(11 22|33) 44"),
+        ] {
+            let nodes = parsing::parse(parsing::yara_pattern(), pattern).unwrap();
+            assert_eq!(super::ida(&nodes), expected, "{pattern}");
+        }
+    }
+}
@@ -5,29 +5,65 @@
     clippy::module_name_repetitions
 )]
 
+mod ast;
+mod convert;
 mod error;
+mod field;
+mod formatting;
 mod needle;
+mod needle_set;
 mod parsing;
 mod pattern;
 mod prefilter;
+mod program;
 mod slice;
+#[cfg(test)]
+mod testing;
 
 mod private {
     pub trait Sealed {}
 }
 
+pub use convert::{
+    ConversionError,
+    Dialect,
+    Feature,
+    Signature,
+    Unrepresentable,
+};
 pub use error::{
     Error,
     Reason,
 };
+#[doc(hidden)]
+pub use field::{
+    RawField,
+    RawScalar,
+};
 pub use needle::{
+    Capture,
     DynamicNeedle,
     Find,
+    FindNonOverlapping,
     Match,
     Needle,
     StaticNeedle,
+    Uniqueness,
+};
+pub use needle_set::{
+    FindSet,
+    NeedleSet,
 };
 pub use pattern::Method;
 #[doc(hidden)]
+pub use pattern::RawByteSet;
+#[doc(hidden)]
 pub use prefilter::RawPrefilter;
 use private::Sealed;
+#[doc(hidden)]
+pub use program::{
+    RawAnchor,
+    RawCaptures,
+    RawInstruction,
+    RawProgram,
+};
@@ -1,30 +1,241 @@
 use crate::{
+    ast::Node,
+    error::SimpleError,
+    field::RawField,
+    formatting,
     parsing,
     pattern::{
         DynamicPattern,
         Method,
+        PatternByte,
         PatternRef,
         StaticPattern,
     },
     prefilter::{
         CompiledPrefilter,
+        Literal,
         PrefilterError,
     },
+    program::{
+        self,
+        Compiled,
+        Program,
+        ProgramRef,
+        Scratch,
+        CURSOR_SLOT,
+        UNSET,
+    },
     Error,
+    RawCaptures,
     RawPrefilter,
+    RawProgram,
     Sealed,
 };
 use chumsky::{
     primitive::end,
     Parser as _,
 };
-use std::ops::Range;
+use std::{
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+    iter::Rev,
+    ops::Range,
+    str::FromStr,
+};
 
-/// Represents a matching [`Needle`] found in the haystack.
+/// The names of a needle's captures, in the order their opening parentheses appear.
+#[derive(Clone, Copy, Debug)]
+enum CaptureNames<'a> {
+    Static(&'a [Option<&'a str>]),
+    Dynamic(&'a [Option<String>]),
+}
+
+impl<'a> CaptureNames<'a> {
+    #[must_use]
+    fn len(self) -> usize {
+        match self {
+            Self::Static(names) => names.len(),
+            Self::Dynamic(names) => names.len(),
+        }
+    }
+
+    /// The name of the capture at `index`, which must be in bounds.
+    #[must_use]
+    fn name(self, index: usize) -> Option<&'a str> {
+        match self {
+            Self::Static(names) => names[index],
+            Self::Dynamic(names) => names[index].as_deref(),
+        }
+    }
+
+    /// The index of the capture called `name`.
+    #[must_use]
+    fn position(self, name: &str) -> Option<usize> {
+        (0..self.len()).find(|&index| self.name(index) == Some(name))
+    }
+}
+
+/// A borrowed view of everything needed to match a [`Needle`] against a haystack, and to locate its captures within a [`Match`].
+#[derive(Clone, Copy, Debug)]
+struct NeedleRef<'a> {
+    pattern: PatternRef<'a>,
+    program: Option<ProgramRef<'a>>,
+    capture_names: CaptureNames<'a>,
+    /// The offset of the cursor from the start of a match, followed by the offsets of the start and end of each capture,
+    /// or empty if the needle has a variable length.
+    capture_offsets: &'a [usize],
+    /// Pairs of offsets from the start of a match whose bytes must be equal, or empty if the needle has a variable length.
+    backreferences: &'a [(usize, usize)],
+    /// The numbers which must be within a range, along with their offsets from the start of a match, or empty if the needle has a variable length.
+    fields: &'a [(usize, RawField)],
+}
+
+impl NeedleRef<'_> {
+    /// Yields the end of the match, if the needle matches the haystack at `start`.
+    ///
+    /// The positions of the cursor and the bounds of each capture are recorded into `slots` for variable length needles.
+    #[must_use]
+    fn matches_at(
+        self,
+        haystack: &[u8],
+        start: usize,
+        scratch: &mut Scratch,
+        slots: &mut [usize],
+    ) -> Option<usize> {
+        if let Some(program) = self.program {
+            slots.fill(UNSET);
+            program.run(haystack, start, scratch, slots)
+        } else {
+            let end = start + self.pattern.len();
+            let haystack = haystack.get(start..end)?;
+            // SAFETY: `haystack` has the same length as `pattern`
+            let matches = unsafe { self.pattern.cmpeq_unchecked(haystack) }
+                && self
+                    .backreferences
+                    .iter()
+                    .all(|&(first, second)| haystack[first] == haystack[second])
+                && self
+                    .fields
+                    .iter()
+                    .all(|(offset, field)| field.contains(&haystack[*offset..]));
+            matches.then_some(end)
+        }
+    }
+
+    /// An Ida style pattern equivalent to the needle, see [`DynamicNeedle::to_ida`].
+    #[must_use]
+    fn to_ida(self) -> String {
+        let names: Vec<_> = (0..self.capture_names.len())
+            .map(|index| self.capture_names.name(index))
+            .collect();
+        let nodes = match self.program {
+            Some(program) => program.decompile(&names),
+            None => program::decompile_fixed(
+                self.pattern.bytes(),
+                &names,
+                self.capture_offsets,
+                self.backreferences,
+                self.fields,
+            ),
+        };
+        formatting::ida(&nodes)
+    }
+
+    /// The bytes and `x`/`?` mask of a code style pattern equivalent to the needle, if it can be expressed as one.
+    #[must_use]
+    fn code_style(self) -> Option<(Vec<u8>, String)> {
+        if self.program.is_some() || !self.backreferences.is_empty() || !self.fields.is_empty() {
+            return None;
+        }
+        self.pattern
+            .word_slice()
+            .iter()
+            .zip(self.pattern.mask_slice())
+            .map(|(&word, &mask)| match u8::from(mask) {
+                0x00 => Some((word, 'x')),
+                0xFF => Some((0x00, '?')),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Represents the part of a [`Match`] found by one of the [`Needle`]'s captures.
 #[derive(Clone, Copy, Debug)]
+pub struct Capture<'haystack> {
+    range: (usize, usize),
+    haystack: &'haystack [u8],
+}
+
+impl<'haystack> Capture<'haystack> {
+    /// The position of the first byte in the capture, relative to the haystack.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("63 (?) 74").unwrap();
+    /// let haystack = "a_cat_tries";
+    /// let matched = needle.find(haystack.as_bytes()).unwrap();
+    /// assert_eq!(matched.capture(0).unwrap().start(), 3);
+    /// ```
+    #[must_use]
+    pub fn start(&self) -> usize {
+        self.range.0
+    }
+
+    /// The position of the last byte past the end of the capture, relative to the haystack.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("63 (?) 74").unwrap();
+    /// let haystack = "a_cat_tries";
+    /// let matched = needle.find(haystack.as_bytes()).unwrap();
+    /// assert_eq!(matched.capture(0).unwrap().end(), 4);
+    /// ```
+    #[must_use]
+    pub fn end(&self) -> usize {
+        self.range.1
+    }
+
+    /// The range of the capture, relative to the haystack.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("63 (?) 74").unwrap();
+    /// let haystack = "a_cat_tries";
+    /// let matched = needle.find(haystack.as_bytes()).unwrap();
+    /// assert_eq!(matched.capture(0).unwrap().range(), 3..4);
+    /// ```
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        self.start()..self.end()
+    }
+
+    /// The actual captured bytes, from the haystack.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("63 (?) 74").unwrap();
+    /// let haystack = "a_cat_tries";
+    /// let matched = needle.find(haystack.as_bytes()).unwrap();
+    /// assert_eq!(matched.capture(0).unwrap().as_bytes(), &b"a"[..]);
+    /// ```
+    #[must_use]
+    pub fn as_bytes(&self) -> &'haystack [u8] {
+        &self.haystack[self.range()]
+    }
+}
+
+/// Represents a matching [`Needle`] found in the haystack.
+#[derive(Clone, Debug)]
 pub struct Match<'haystack> {
     range: (usize, usize),
     haystack: &'haystack [u8],
+    cursor: usize,
+    /// The start and end of each capture, relative to the haystack.
+    captures: Box<[(usize, usize)]>,
 }
 
 impl<'haystack> Match<'haystack> {
@@ -83,11 +294,96 @@ impl<'haystack> Match<'haystack> {
     pub fn as_bytes(&self) -> &'haystack [u8] {
         &self.haystack[self.range()]
     }
+
+    /// The number of captures in the needle.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("(63) ? (74)").unwrap();
+    /// let haystack = "a_cat_tries";
+    /// let matched = needle.find(haystack.as_bytes()).unwrap();
+    /// assert_eq!(matched.captures_len(), 2);
+    /// ```
+    #[must_use]
+    pub fn captures_len(&self) -> usize {
+        self.captures.len()
+    }
+
+    /// The capture at `index`, where captures are numbered in the order their opening parentheses appear in the pattern.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("(63 (?)) 74").unwrap();
+    /// let haystack = "a_cat_tries";
+    /// let matched = needle.find(haystack.as_bytes()).unwrap();
+    /// assert_eq!(matched.capture(0).unwrap().as_bytes(), &b"ca"[..]);
+    /// assert_eq!(matched.capture(1).unwrap().as_bytes(), &b"a"[..]);
+    /// assert!(matched.capture(2).is_none());
+    /// ```
+    #[must_use]
+    pub fn capture(&self, index: usize) -> Option<Capture<'haystack>> {
+        self.captures.get(index).map(|&range| Capture {
+            range,
+            haystack: self.haystack,
+        })
+    }
+
+    /// Every capture, in the order their opening parentheses appear in the pattern.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("(63) [0-2] (74)").unwrap();
+    /// let haystack = "a_cat_tries";
+    /// let matched = needle.find(haystack.as_bytes()).unwrap();
+    /// let captures: Vec<_> = matched.captures().map(|x| x.range()).collect();
+    /// assert_eq!(captures, [2..3, 4..5]);
+    /// ```
+    pub fn captures(&self) -> impl Iterator<Item = Capture<'haystack>> + '_ {
+        self.captures.iter().map(|&range| Capture {
+            range,
+            haystack: self.haystack,
+        })
+    }
+
+    /// The position of the needle's cursor, relative to the haystack.
+    ///
+    /// This is the same as [`Match::start`] if the needle doesn't have a cursor.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("5F ^ 63 ? 74").unwrap();
+    /// let haystack = "a_cat_tries";
+    /// let matched = needle.find(haystack.as_bytes()).unwrap();
+    /// assert_eq!(matched.range(), 1..5);
+    /// assert_eq!(matched.cursor(), 2);
+    ///
+    /// let needle = DynamicNeedle::from_ida("63 ? 74").unwrap();
+    /// let matched = needle.find(haystack.as_bytes()).unwrap();
+    /// assert_eq!(matched.cursor(), matched.start());
+    /// ```
+    #[must_use]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+}
+
+/// The result of searching for a [`Needle`] which should only match once, see [`Needle::find_unique`].
+#[derive(Clone, Debug)]
+pub enum Uniqueness<'haystack> {
+    /// The needle doesn't match anywhere in the haystack.
+    NotFound,
+    /// The needle matches exactly once.
+    Unique(Match<'haystack>),
+    /// The needle matches more than once, where these are the first two matches.
+    Ambiguous {
+        first: Match<'haystack>,
+        second: Match<'haystack>,
+    },
 }
 
 /// The common interface for searching haystacks with needles.
 ///
-/// A successful search will yield a [`Match`] in the haystack, whose length is equal to the [length](Needle::len) of the needle. Matches may overlap.
+/// A successful search will yield a [`Match`] in the haystack, whose length is equal to the [length](Needle::len) of the needle, unless the needle has a variable length. Matches may overlap.
 ///
 /// ```
 /// # use aob_common::{DynamicNeedle, Needle as _};
@@ -113,83 +409,434 @@ pub trait Needle: Sealed {
         haystack: &'haystack [u8],
     ) -> Find<'needle, 'haystack>;
 
+    /// Finds all matching subsequences which don't overlap each other, iteratively.
+    ///
+    /// Each search resumes at the end of the previous match, so the leftmost of any overlapping matches is kept.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("90 90").unwrap();
+    /// let haystack = [0x90; 5];
+    /// assert_eq!(needle.find_iter(&haystack).count(), 4);
+    /// let matches: Vec<_> = needle
+    ///     .find_iter_non_overlapping(&haystack)
+    ///     .map(|x| x.range())
+    ///     .collect();
+    /// assert_eq!(matches, [0..2, 2..4]);
+    /// ```
+    #[must_use]
+    fn find_iter_non_overlapping<'needle, 'haystack>(
+        &'needle self,
+        haystack: &'haystack [u8],
+    ) -> FindNonOverlapping<'needle, 'haystack> {
+        FindNonOverlapping {
+            find: self.find_iter(haystack),
+        }
+    }
+
+    /// Finds all matching subsequences which start at a multiple of `alignment` plus `remainder`, iteratively.
+    ///
+    /// Positions yielded by the prefilter which can't lead to an aligned match are skipped before anything else is compared.
+    ///
+    /// # Panics
+    /// * Panics if `alignment` is zero.
+    /// * Panics if `remainder` isn't less than `alignment`.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("C3").unwrap();
+    /// let haystack = [0xC3, 0xC3, 0x90, 0xC3, 0xC3, 0x90];
+    /// let matches: Vec<_> = needle
+    ///     .find_iter_aligned(&haystack, 2, 0)
+    ///     .map(|x| x.start())
+    ///     .collect();
+    /// assert_eq!(matches, [0, 4]);
+    /// let matches: Vec<_> = needle
+    ///     .find_iter_aligned(&haystack, 2, 1)
+    ///     .map(|x| x.start())
+    ///     .collect();
+    /// assert_eq!(matches, [1, 3]);
+    /// ```
+    #[must_use]
+    fn find_iter_aligned<'needle, 'haystack>(
+        &'needle self,
+        haystack: &'haystack [u8],
+        alignment: usize,
+        remainder: usize,
+    ) -> Find<'needle, 'haystack> {
+        assert!(alignment != 0, "alignment must not be zero");
+        assert!(
+            remainder < alignment,
+            "remainder {remainder} must be less than alignment {alignment}"
+        );
+        let mut iter = self.find_iter(haystack);
+        iter.alignment = alignment;
+        iter.remainder = remainder;
+        iter
+    }
+
+    /// A convenience method for getting only the first match which starts at or after `offset`.
+    ///
+    /// The match is positioned relative to the whole haystack, not to `offset`.
+    ///
+    /// # Panics
+    /// Panics if `offset` is past the end of the haystack.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("63 ? 74").unwrap();
+    /// let haystack = "a_cat_cut";
+    /// let matched = needle.find_at(haystack.as_bytes(), 3).unwrap();
+    /// assert_eq!(matched.range(), 6..9);
+    /// ```
+    #[must_use]
+    fn find_at<'haystack>(
+        &self,
+        haystack: &'haystack [u8],
+        offset: usize,
+    ) -> Option<Match<'haystack>> {
+        self.find_iter_in(haystack, offset..haystack.len()).next()
+    }
+
+    /// Finds all matching subsequences which lie entirely within `range` of the haystack, iteratively.
+    ///
+    /// Matches, and their captures, are positioned relative to the whole haystack rather than to `range`.
+    ///
+    /// # Panics
+    /// Panics if `range` is out of bounds of the haystack.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("63 ? 74").unwrap();
+    /// let haystack = "cat_cut_cot";
+    /// let matches: Vec<_> = needle
+    ///     .find_iter_in(haystack.as_bytes(), 2..10)
+    ///     .map(|x| x.range())
+    ///     .collect();
+    /// assert_eq!(matches, [4..7]);
+    /// ```
+    #[must_use]
+    fn find_iter_in<'needle, 'haystack>(
+        &'needle self,
+        haystack: &'haystack [u8],
+        range: Range<usize>,
+    ) -> Find<'needle, 'haystack> {
+        assert!(
+            range.start <= range.end && range.end <= haystack.len(),
+            "range {range:?} is out of bounds of a haystack of length {}",
+            haystack.len()
+        );
+        // matches are only ever checked forwards from their start, so the bytes before the range can be kept
+        let mut iter = self.find_iter(&haystack[..range.end]);
+        iter.last_offset = range.start;
+        iter
+    }
+
+    /// A convenience method for getting only the last match.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("C3").unwrap();
+    /// let haystack = [0xC3, 0x90, 0xC3, 0x90, 0x90];
+    /// let matched = needle.rfind(&haystack).unwrap();
+    /// assert_eq!(matched.start(), 2);
+    /// ```
+    #[must_use]
+    fn rfind<'haystack>(&self, haystack: &'haystack [u8]) -> Option<Match<'haystack>> {
+        self.rfind_iter(haystack).next()
+    }
+
+    /// Finds the only match in the haystack, or reports whether there were none or too many.
+    ///
+    /// The search stops as soon as a second match is found. Like [`Needle::find_iter`], overlapping matches are counted separately.
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _, Uniqueness};
+    /// let needle = DynamicNeedle::from_ida("63 ? 74").unwrap();
+    /// let Uniqueness::Unique(matched) = needle.find_unique(b"a_cat_tries") else {
+    ///     panic!();
+    /// };
+    /// assert_eq!(matched.range(), 2..5);
+    /// assert!(matches!(
+    ///     needle.find_unique(b"a_cat_cut"),
+    ///     Uniqueness::Ambiguous { .. }
+    /// ));
+    /// assert!(matches!(needle.find_unique(b"a_dog"), Uniqueness::NotFound));
+    /// ```
+    #[must_use]
+    fn find_unique<'haystack>(&self, haystack: &'haystack [u8]) -> Uniqueness<'haystack> {
+        let mut iter = self.find_iter(haystack);
+        let Some(first) = iter.next() else {
+            return Uniqueness::NotFound;
+        };
+        match iter.next() {
+            Some(second) => Uniqueness::Ambiguous { first, second },
+            None => Uniqueness::Unique(first),
+        }
+    }
+
+    /// Finds all matching subsequences, iteratively, starting from the end of the haystack.
+    ///
+    /// This yields the same matches as [`Needle::find_iter`], in reverse order.
+    #[must_use = "iterators are lazy and do nothing unless consumed"]
+    fn rfind_iter<'needle, 'haystack>(
+        &'needle self,
+        haystack: &'haystack [u8],
+    ) -> Rev<Find<'needle, 'haystack>> {
+        self.find_iter(haystack).rev()
+    }
+
     /// The length of the needle itself.
     ///
+    /// For needles with a variable length, this is the shortest length a match can have.
+    ///
     /// ```
     /// # use aob_common::{DynamicNeedle, Needle as _};
     /// let needle = DynamicNeedle::from_ida("12 ? 56 ? 9A BC").unwrap();
     /// assert_eq!(needle.len(), 6);
+    /// let needle = DynamicNeedle::from_yara("12 [2-4] 9A BC").unwrap();
+    /// assert_eq!(needle.len(), 5);
     /// ```
     #[must_use]
     fn len(&self) -> usize;
+
+    /// The index of the capture called `name`, see [`Match::capture`].
+    ///
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_ida("48 8B 05 (disp: ? ? ? ?)").unwrap();
+    /// let haystack = [0x90, 0x48, 0x8B, 0x05, 0x78, 0x56, 0x34, 0x12];
+    /// let matched = needle.find(&haystack).unwrap();
+    /// let disp = matched.capture(needle.capture_index("disp").unwrap()).unwrap();
+    /// assert_eq!(disp.range(), 4..8);
+    /// assert_eq!(i32::from_le_bytes(disp.as_bytes().try_into().unwrap()), 0x12345678);
+    /// assert!(needle.capture_index("imm").is_none());
+    /// ```
+    #[must_use]
+    fn capture_index(&self, name: &str) -> Option<usize>;
 }
 
 /// An [`Iterator`] for finding subsequent matches of a [`Needle`] in a haystack.
+///
+/// Matches can also be taken from the end of the haystack, see [`Needle::rfind_iter`].
 pub struct Find<'needle, 'haystack> {
     prefilter: CompiledPrefilter,
-    pattern: PatternRef<'needle>,
+    needle: NeedleRef<'needle>,
+    len: usize,
     haystack: &'haystack [u8],
+    /// The least start of a match which may be yielded from the front.
     last_offset: usize,
+    /// One past the greatest start of a match which may be yielded from the back.
+    back_offset: usize,
+    /// Matches may only start at a multiple of `alignment`, plus `remainder`.
+    alignment: usize,
+    remainder: usize,
+    scratch: Scratch,
+    slots: Vec<usize>,
 }
 
-impl Find<'_, '_> {
+impl<'needle, 'haystack> Find<'needle, 'haystack> {
+    #[must_use]
+    fn new(
+        prefilter: CompiledPrefilter,
+        needle: NeedleRef<'needle>,
+        len: usize,
+        haystack: &'haystack [u8],
+    ) -> Self {
+        Self {
+            prefilter,
+            needle,
+            len,
+            haystack,
+            last_offset: 0,
+            back_offset: (haystack.len() + 1).saturating_sub(len),
+            alignment: 1,
+            remainder: 0,
+            scratch: needle
+                .program
+                .map(|program| program.scratch())
+                .unwrap_or_default(),
+            slots: vec![0; program::slots_len(needle.capture_names.len())],
+        }
+    }
+
     /// Yields the [`Method`] chosen for quick string comparison of the [`Needle`] against strings in the haystack.
+    ///
+    /// For needles with a variable length, this is the method chosen for the chunk of the needle that the prefilter searches for.
     #[must_use]
     pub fn search_method(&self) -> Method {
-        self.pattern.method()
+        match self.needle.program {
+            Some(program) => program
+                .anchor_chunk()
+                .map_or(Method::Scalar, |chunk| chunk.method()),
+            None => self.needle.pattern.method(),
+        }
     }
-}
 
-impl<'haystack> Iterator for Find<'_, 'haystack> {
-    type Item = Match<'haystack>;
+    /// The range of distances between the start of a match, and the position yielded by the prefilter.
+    #[must_use]
+    fn anchor_distance(&self) -> (usize, Option<usize>) {
+        self.needle
+            .program
+            .and_then(|program| program.anchor())
+            .map_or((0, Some(0)), |anchor| {
+                (anchor.min_distance, anchor.max_distance)
+            })
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        macro_rules! failure {
-            () => {{
-                self.last_offset = self.haystack.len();
-                return None;
-            }};
-        }
+    /// The longest run of exact bytes that the prefilter would search within, along with the greatest distance between the start of a match and the position the prefilter yields.
+    ///
+    /// Yields `None` if the needle can't be searched for by a literal alone.
+    #[must_use]
+    pub(crate) fn literal(&self) -> Option<(Literal, usize)> {
+        let pattern = match self.needle.program {
+            Some(program) => program.anchor_chunk()?,
+            None => self.needle.pattern,
+        };
+        let (_, max_distance) = self.anchor_distance();
+        Some((Literal::from_pattern(pattern)?, max_distance?))
+    }
 
-        macro_rules! success {
-            ($start:ident, $end:ident) => {{
-                self.last_offset = $start + 1;
-                return Some(Match {
-                    range: ($start, $end),
-                    haystack: self.haystack,
-                });
-            }};
+    /// The match from `start` to `end`, keeping the slots recorded while it was matched.
+    #[must_use]
+    fn found(&self, start: usize, end: usize) -> Match<'haystack> {
+        let position = |slot| match self.needle.program {
+            // slots which the match doesn't pass through are left at its start
+            Some(_) => Some(self.slots[slot])
+                .filter(|&x| x != UNSET)
+                .unwrap_or(start),
+            None => self
+                .needle
+                .capture_offsets
+                .get(slot)
+                .map_or(start, |offset| start + offset),
+        };
+        Match {
+            range: (start, end),
+            haystack: self.haystack,
+            cursor: position(CURSOR_SLOT),
+            captures: (0..self.needle.capture_names.len())
+                .map(|index| {
+                    let slot = program::slots_len(index);
+                    (position(slot), position(slot + 1))
+                })
+                .collect(),
         }
+    }
 
-        let mut prefilter_iter = self.prefilter.find_iter(&self.haystack[self.last_offset..]);
-        loop {
-            let prefilter_offset = match prefilter_iter.next() {
-                Some(Ok(offset)) => offset,
-                Some(Err(PrefilterError::HaystackTooSmall { offset })) => {
-                    self.last_offset += offset;
-                    break;
-                }
-                None => failure!(),
-            };
-            let start = self.last_offset + prefilter_offset;
-            let end = start + self.pattern.len();
-            let Some(haystack) = &self.haystack.get(start..end) else {
-                failure!();
+    /// Searches the same needle within a different haystack, from the beginning.
+    #[must_use]
+    pub(crate) fn with_haystack<'other>(&self, haystack: &'other [u8]) -> Find<'needle, 'other> {
+        Find::new(self.prefilter, self.needle, self.len, haystack)
+    }
+
+    /// Yields the next match that begins within range of `anchor`, a position yielded by the prefilter.
+    ///
+    /// Positions must be given in ascending order, and the same position should be given again until it yields nothing.
+    pub(crate) fn next_at_anchor(&mut self, anchor: usize) -> Option<Match<'haystack>> {
+        let (min_distance, max_distance) = self.anchor_distance();
+        let last_start = anchor.checked_sub(min_distance)?;
+        let first_start = self.align_up(max_distance.map_or(self.last_offset, |max_distance| {
+            self.last_offset.max(anchor.saturating_sub(max_distance))
+        }));
+        // anchors without an aligned start in range are skipped before their chunk is even compared
+        if first_start <= last_start {
+            if !self.anchor_matches(anchor) {
+                return None;
+            }
+            for start in (first_start..=last_start).step_by(self.alignment) {
+                if start >= self.back_offset {
+                    self.last_offset = start;
+                    return None;
+                }
+                if let Some(end) =
+                    self.needle
+                        .matches_at(self.haystack, start, &mut self.scratch, &mut self.slots)
+                {
+                    self.last_offset = start + 1;
+                    return Some(self.found(start, end));
+                }
+            }
+        }
+        self.last_offset = self.last_offset.max(last_start + 1);
+        None
+    }
+
+    /// The first position at or after `pos` where a match may start, given the alignment.
+    #[must_use]
+    fn align_up(&self, pos: usize) -> usize {
+        pos + (self.remainder + self.alignment - pos % self.alignment) % self.alignment
+    }
+
+    /// Whether the chunk of a variable length needle that the prefilter searches for matches the haystack at `pos`.
+    #[must_use]
+    fn anchor_matches(&self, pos: usize) -> bool {
+        let Some(chunk) = self
+            .needle
+            .program
+            .and_then(|program| program.anchor_chunk())
+        else {
+            return true;
+        };
+        match self.haystack.get(pos..pos + chunk.len()) {
+            // SAFETY: `haystack` has the same length as `chunk`
+            Some(haystack) => unsafe { chunk.cmpeq_unchecked(haystack) },
+            None => false,
+        }
+    }
+}
+
+impl<'haystack> Iterator for Find<'_, 'haystack> {
+    type Item = Match<'haystack>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        macro_rules! failure {
+            () => {{
+                self.last_offset = self.back_offset;
+                return None;
+            }};
+        }
+
+        macro_rules! success {
+            ($start:ident, $end:ident) => {{
+                self.last_offset = $start + 1;
+                return Some(self.found($start, $end));
+            }};
+        }
+
+        // the prefilter yields the positions of the anchor, which any match must begin a bounded distance before
+        let (min_distance, max_distance) = self.anchor_distance();
+        let base = self.align_up(self.last_offset) + min_distance;
+        let Some(prefilter_haystack) = self.haystack.get(base..) else {
+            failure!();
+        };
+        let prefilter = self.prefilter;
+        let mut prefilter_iter = prefilter.find_iter(prefilter_haystack);
+        loop {
+            let anchor = match prefilter_iter.next() {
+                Some(Ok(offset)) => base + offset,
+                Some(Err(PrefilterError::HaystackTooSmall { offset })) => {
+                    if let Some(max_distance) = max_distance {
+                        let start = (base + offset).saturating_sub(max_distance);
+                        self.last_offset = self.last_offset.max(start);
+                    }
+                    break;
+                }
+                None => failure!(),
             };
-            // SAFETY: `haystack` has the same length as `self.pattern`
-            if unsafe { self.pattern.cmpeq_unchecked(haystack) } {
-                success!(start, end);
+            if let Some(found) = self.next_at_anchor(anchor) {
+                return Some(found);
+            }
+            if self.last_offset >= self.back_offset {
+                failure!();
             }
         }
 
-        for (window_offset, window) in self.haystack[self.last_offset..]
-            .windows(self.pattern.len())
-            .enumerate()
-        {
-            // SAFETY: `window` has the same length as `self.pattern`
-            if unsafe { self.pattern.cmpeq_unchecked(window) } {
-                let start = self.last_offset + window_offset;
-                let end = start + self.pattern.len();
+        for start in (self.align_up(self.last_offset)..self.back_offset).step_by(self.alignment) {
+            if let Some(end) =
+                self.needle
+                    .matches_at(self.haystack, start, &mut self.scratch, &mut self.slots)
+            {
                 success!(start, end);
             }
         }
@@ -198,6 +845,79 @@ impl<'haystack> Iterator for Find<'_, 'haystack> {
     }
 }
 
+impl DoubleEndedIterator for Find<'_, '_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // searching backwards, the last anchor before any unchecked start is the first one which might find it
+        let (min_distance, max_distance) = self.anchor_distance();
+        let mut end = max_distance.map_or(self.haystack.len() + 1, |max_distance| {
+            self.back_offset + max_distance
+        });
+        while self.last_offset < self.back_offset {
+            let Some(anchor) = self.prefilter.rfind(self.haystack, end) else {
+                break;
+            };
+            end = anchor;
+            let Some(last_start) = anchor.checked_sub(min_distance) else {
+                break;
+            };
+            if last_start < self.last_offset {
+                break;
+            }
+
+            let last_start = last_start.min(self.back_offset - 1);
+            let first_start = max_distance.map_or(self.last_offset, |max_distance| {
+                self.last_offset.max(anchor.saturating_sub(max_distance))
+            });
+            let aligned_start = self.align_up(first_start);
+            if aligned_start <= last_start {
+                if !self.anchor_matches(anchor) {
+                    continue;
+                }
+                let last_start = last_start - (last_start - aligned_start) % self.alignment;
+                for start in (aligned_start..=last_start).rev().step_by(self.alignment) {
+                    if let Some(end) = self.needle.matches_at(
+                        self.haystack,
+                        start,
+                        &mut self.scratch,
+                        &mut self.slots,
+                    ) {
+                        self.back_offset = start;
+                        return Some(self.found(start, end));
+                    }
+                }
+            }
+            self.back_offset = first_start;
+        }
+
+        self.back_offset = self.last_offset;
+        None
+    }
+}
+
+/// An [`Iterator`] for finding subsequent matches of a [`Needle`] in a haystack, which don't overlap each other.
+pub struct FindNonOverlapping<'needle, 'haystack> {
+    find: Find<'needle, 'haystack>,
+}
+
+impl FindNonOverlapping<'_, '_> {
+    /// See [`Find::search_method`].
+    #[must_use]
+    pub fn search_method(&self) -> Method {
+        self.find.search_method()
+    }
+}
+
+impl<'haystack> Iterator for FindNonOverlapping<'_, 'haystack> {
+    type Item = Match<'haystack>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let found = self.find.next()?;
+        // the prefilter resumes past the match, rather than searching within it again
+        self.find.last_offset = found.end().max(found.start() + 1);
+        Some(found)
+    }
+}
+
 /// The compile-time variant of a [`Needle`].
 ///
 /// [`StaticNeedle`] is intended for embedding into executables at compile-time,
@@ -211,6 +931,8 @@ impl<'haystack> Iterator for Find<'_, 'haystack> {
 pub struct StaticNeedle<const NEEDLE_LEN: usize, const BUFFER_LEN: usize> {
     prefilter: RawPrefilter,
     pattern: StaticPattern<NEEDLE_LEN, BUFFER_LEN>,
+    program: Option<RawProgram<'static>>,
+    captures: RawCaptures<'static>,
 }
 
 impl<const NEEDLE_LEN: usize, const BUFFER_LEN: usize> StaticNeedle<NEEDLE_LEN, BUFFER_LEN> {
@@ -221,14 +943,55 @@ impl<const NEEDLE_LEN: usize, const BUFFER_LEN: usize> StaticNeedle<NEEDLE_LEN,
         prefilter: RawPrefilter,
         word: [u8; BUFFER_LEN],
         mask: [u8; BUFFER_LEN],
+        program: Option<RawProgram<'static>>,
+        captures: RawCaptures<'static>,
     ) -> Self {
         Self {
             prefilter,
             pattern: StaticPattern::from_components(word, mask),
+            program,
+            captures,
+        }
+    }
+
+    /// Formats the needle as an Ida style pattern, see [`DynamicNeedle::to_ida`].
+    #[must_use]
+    pub fn to_ida(&self) -> String {
+        self.needle_ref().to_ida()
+    }
+
+    /// Formats the needle as a code style pattern, see [`DynamicNeedle::to_code_style`].
+    #[must_use]
+    pub fn to_code_style(&self) -> Option<(Vec<u8>, String)> {
+        self.needle_ref().code_style()
+    }
+
+    #[must_use]
+    fn needle_ref(&self) -> NeedleRef<'_> {
+        let pattern: PatternRef<'_> = (&self.pattern).into();
+        // SAFETY: the program was compiled alongside the pattern's buffers
+        let program = self
+            .program
+            .map(|program| unsafe { ProgramRef::new(program, pattern) });
+        NeedleRef {
+            pattern,
+            program,
+            capture_names: CaptureNames::Static(self.captures.names),
+            capture_offsets: self.captures.offsets,
+            backreferences: self.captures.backreferences,
+            fields: self.captures.fields,
         }
     }
 }
 
+impl<const NEEDLE_LEN: usize, const BUFFER_LEN: usize> Display
+    for StaticNeedle<NEEDLE_LEN, BUFFER_LEN>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_ida())
+    }
+}
+
 impl<const NEEDLE_LEN: usize, const BUFFER_LEN: usize> Sealed
     for StaticNeedle<NEEDLE_LEN, BUFFER_LEN>
 {
@@ -241,7 +1004,7 @@ impl<const NEEDLE_LEN: usize, const BUFFER_LEN: usize> Needle
         &'needle self,
         haystack: &'haystack [u8],
     ) -> Find<'needle, 'haystack> {
-        let pattern: PatternRef<'_> = (&self.pattern).into();
+        let needle = self.needle_ref();
         let prefilter = match self.prefilter {
             RawPrefilter::Length { len } => CompiledPrefilter::from_length(len),
             RawPrefilter::Prefix {
@@ -254,22 +1017,25 @@ impl<const NEEDLE_LEN: usize, const BUFFER_LEN: usize> Needle
                 postfix: _,
                 postfix_offset,
             } => CompiledPrefilter::from_prefix_postfix(
-                pattern.word_slice(),
+                needle
+                    .program
+                    .and_then(|program| program.anchor_chunk())
+                    .unwrap_or(needle.pattern)
+                    .word_slice(),
                 prefix_offset.into(),
                 postfix_offset.into(),
             ),
         };
-        Find {
-            prefilter,
-            pattern,
-            haystack,
-            last_offset: 0,
-        }
+        Find::new(prefilter, needle, NEEDLE_LEN, haystack)
     }
 
     fn len(&self) -> usize {
         NEEDLE_LEN
     }
+
+    fn capture_index(&self, name: &str) -> Option<usize> {
+        CaptureNames::Static(self.captures.names).position(name)
+    }
 }
 
 /// The run-time variant of a [`Needle`].
@@ -277,15 +1043,63 @@ impl<const NEEDLE_LEN: usize, const BUFFER_LEN: usize> Needle
 pub struct DynamicNeedle {
     prefilter: CompiledPrefilter,
     pattern: DynamicPattern,
+    program: Option<Program>,
+    len: usize,
+    capture_names: Vec<Option<String>>,
+    capture_offsets: Vec<usize>,
+    backreferences: Vec<(usize, usize)>,
+    fields: Vec<(usize, RawField)>,
 }
 
 impl DynamicNeedle {
     /// Construct a [`DynamicNeedle`] using an Ida style pattern.
     ///
     /// # Syntax
-    /// Expects a sequence of `byte` or `wildcard` separated by whitespace, where:
+    /// Expects a sequence of `byte`, `wildcard`, `named wildcard`, `gap`, `set`, `negation`, `capture`, `alternation`, `cursor`, `string`, or `typed value` separated by whitespace, where:
     /// * `byte` is exactly 2 hexadecimals (uppercase or lowercase), indicating an exact match
+    ///   * either hexadecimal may be replaced with a `?`, indicating a fuzzy match on only that nibble
     /// * `wildcard` is one or two `?` characters, indicating a fuzzy match
+    /// * `named wildcard` is a `?` followed by a name enclosed in angle brackets, e.g. `?<reg>`, indicating a fuzzy match on a byte which must equal every other byte matched by a `named wildcard` of the same name
+    ///   * the `named wildcard`s of each name are together a single capture of that name, see [`Needle::capture_index`],
+    ///     which holds the first one outside of any alternation, or else the last one matched
+    /// * `gap` is `[min-max]` or `[len]` in decimal, indicating a fuzzy match on a variable number of bytes
+    ///   * the bounds of a `gap` follow the same rules as a `jump` in [`DynamicNeedle::from_yara`]
+    /// * `set` is one or more `byte` or `first-last` ranges separated by `|` and enclosed in parentheses, e.g. `(E8|E9)` or `(00-1F)`, indicating a match on any of them
+    ///   * the bounds of a range must be exact, and `first` must not be greater than `last`
+    /// * `negation` is a `!` followed by a `byte` or `set`, e.g. `!CC` or `!(E8|E9)`, indicating a match on any byte except them
+    /// * `capture` is a sequence enclosed in parentheses, optionally beginning with a name followed by a `:`, e.g. `(? ? ? ?)` or `(disp: ? ? ? ?)`, indicating a match on the sequence
+    ///   * the bytes matched by each capture can be retrieved using [`Match::capture`], and named captures can be found using [`Needle::capture_index`]
+    ///   * a name may only be given to one capture, and not to a `named wildcard` as well
+    ///   * a sequence which is also a valid `set`, e.g. `(E8)`, is treated as a capture
+    /// * `alternation` is two or more sequences separated by `|` and enclosed in parentheses, e.g. `(E8 ? ? ? ?|EB ?)`, indicating a match on any one of the sequences
+    ///   * this is the same as an `alternation` in [`DynamicNeedle::from_yara`], so that a needle constructed from a YARA pattern can be written as an Ida style pattern
+    ///   * alternatives are preferred in the same order as in [`DynamicNeedle::from_yara`]
+    ///   * an alternation which is also a valid `set`, e.g. `(E8|E9)`, is treated as a set
+    ///   * a `named wildcard` within an alternation only needs to equal those outside of any alternation
+    /// * captures and alternations may be nested at most 32 deep
+    /// * a `byte`, `wildcard`, `set`, or `negation` may be followed by a repetition count of `{n}` in decimal, e.g. `?{16}` or `CC{8}`, indicating `n` of that token in a row
+    ///   * `n` must be from 1 to 65535
+    /// * `cursor` is a `^`, indicating the position within a match reported by [`Match::cursor`]
+    ///   * a pattern may have at most one `cursor`
+    /// * `string` is text enclosed in double quotes, e.g. `"MZ"`, indicating an exact match on the text encoded as UTF-8
+    ///   * a `string` prefixed with `u` or `u16le`, e.g. `u"Config"`, is instead encoded as little endian UTF-16, and one prefixed with `u16be` as big endian UTF-16
+    ///   * `\\`, `\"`, `\0`, `\n`, `\r`, and `\t` are escaped as in Rust, and an unprefixed `string` may include any byte as `\xHH`
+    /// * `typed value` is a type followed by a value enclosed in parentheses, e.g. `u32le(0xDEADBEEF)`, `i16be(-2)`, or `f32(1.5)`, indicating an exact match on the bytes encoding that value
+    ///   * the type is `u` (unsigned), `i` (signed), or `f` (floating point), then a size of 8, 16, 32, or 64 bits (only 32 or 64 for `f`), then an optional `le` or `be` suffix for little or big endian, which defaults to little endian
+    ///   * the value is a decimal number, a `0x` prefixed hexadecimal where any digit may be a `?` to indicate a fuzzy match on that nibble, or a `?` to indicate a fuzzy match on the whole value
+    ///   * the value must fit within the type
+    ///   * the value may instead be a range of `min..max` or `min..=max`, e.g. `u32le(0x40..=0x400)`, indicating a match on any value within it
+    ///     * either bound of `min..max` may be left out, e.g. `i16(..0)` or `f32(0.5..)`, in which case the range extends to the limit of the type
+    ///     * the bounds of a range must be exact, and `min` must not be greater than `max`
+    ///     * bits shared by every value within the range are compared alongside the rest of the needle, then the range itself is checked separately
+    ///
+    /// Anything from a `;` or `//` up until the end of the line is a comment, and is treated as whitespace. This allows patterns to be laid out over multiple lines.
+    ///
+    /// Sets are compared alongside the rest of the needle using the same [`Method`], then any set which can't be described by masking some bits of a byte is checked separately.
+    ///
+    /// Matches of a needle with a `gap` can vary in length, so check [`Match::end`] rather than relying on [`Needle::len`].
+    /// Whatever follows an unbounded `gap` is only searched for once per haystack, unless it has a `named wildcard` which must equal one before the `gap`,
+    /// in which case it is searched for again from every possible start of a match.
     ///
     /// # Example
     /// ```
@@ -294,24 +1108,124 @@ impl DynamicNeedle {
     /// let haystack = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE];
     /// let matched = needle.find(&haystack).unwrap();
     /// assert_eq!(&haystack[matched.start()..], [0x78, 0x9A, 0xBC, 0xDE]);
+    ///
+    /// let needle = DynamicNeedle::from_ida("?6 7? ?A").unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(&haystack[matched.start()..], [0x56, 0x78, 0x9A, 0xBC, 0xDE]);
+    ///
+    /// let needle = DynamicNeedle::from_ida("34 [1-3] BC").unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(matched.as_bytes(), [0x34, 0x56, 0x78, 0x9A, 0xBC]);
+    ///
+    /// let needle = DynamicNeedle::from_ida("(00-77|9A) (9A|BC)").unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(matched.as_bytes(), [0x9A, 0xBC]);
+    ///
+    /// let needle = DynamicNeedle::from_ida("!12 !78").unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(matched.as_bytes(), [0x34, 0x56]);
+    ///
+    /// let needle = DynamicNeedle::from_ida("56 (value: ? ?) BC").unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// let value = needle.capture_index("value").unwrap();
+    /// assert_eq!(matched.capture(value).unwrap().as_bytes(), [0x78, 0x9A]);
+    ///
+    /// let needle = DynamicNeedle::from_ida("(12 34|56) 78").unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(matched.as_bytes(), [0x56, 0x78]);
+    ///
+    /// let needle = DynamicNeedle::from_ida("?<x> ? ?<x>").unwrap();
+    /// let matched = needle.find(&[0x12, 0x34, 0x56, 0x34, 0x78, 0x34]).unwrap();
+    /// assert_eq!(matched.range(), 1..4);
+    ///
+    /// let needle = DynamicNeedle::from_ida("?{2} 78 9A").unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(matched.as_bytes(), [0x34, 0x56, 0x78, 0x9A]);
+    ///
+    /// let needle = DynamicNeedle::from_ida("34 ? ^ 78").unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(matched.cursor(), 3);
+    ///
+    /// let needle = DynamicNeedle::from_ida("u16be(0x3456) i8(?) u16(0xBC9A)").unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(matched.as_bytes(), [0x34, 0x56, 0x78, 0x9A, 0xBC]);
+    ///
+    /// let needle = DynamicNeedle::from_ida("u16be(0x3400..0x3500) u16le(0x9A00..)").unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(matched.as_bytes(), [0x34, 0x56, 0x78, 0x9A]);
+    ///
+    /// let needle = DynamicNeedle::from_ida(r#"? "cat" 00 u"A""#).unwrap();
+    /// let matched = needle.find(b"a cat\0A\0").unwrap();
+    /// assert_eq!(matched.range(), 1..8);
+    ///
+    /// let needle = DynamicNeedle::from_ida(
+    ///     "
+    ///     56       ; push rsi
+    ///     78 9A    // some other instruction
+    ///     ",
+    /// )
+    /// .unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(matched.as_bytes(), [0x56, 0x78, 0x9A]);
     /// ```
     pub fn from_ida(pattern: &str) -> Result<Self, Error<'_>> {
         let parser = parsing::ida_pattern().then_ignore(end());
         match parser.parse(pattern) {
-            Ok(ok) => Ok(Self::from_bytes(&ok)),
+            Ok(ok) => Ok(Self::from_nodes(&ok)),
             Err(mut errors) => {
                 let error = errors
                     .drain(..)
                     .next()
                     .expect("failure to parse should produce at least one error");
                 Err(Error {
-                    source: pattern,
+                    source: pattern.into(),
                     inner: error,
                 })
             }
         }
     }
 
+    /// Construct a [`DynamicNeedle`] using a code style pattern, i.e. a string of bytes paired with a mask.
+    ///
+    /// # Syntax
+    /// Expects `bytes` and `mask` to have the same length, where each character in `mask` is one of:
+    /// * `x` (uppercase or lowercase), indicating an exact match on the corresponding byte
+    /// * `?`, indicating a fuzzy match on the corresponding byte
+    ///
+    /// Any errors are reported relative to `mask`.
+    ///
+    /// # Example
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_code_style(b"\x78\x00\xBC", "x?x").unwrap();
+    /// let haystack = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE];
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(&haystack[matched.start()..], [0x78, 0x9A, 0xBC, 0xDE]);
+    /// ```
+    pub fn from_code_style<'a>(bytes: &[u8], mask: &'a str) -> Result<Self, Error<'a>> {
+        let parsed = parsing::parse(parsing::code_style_mask(), mask)?;
+        if parsed.len() != bytes.len() {
+            let span = bytes.len().min(parsed.len())..parsed.len();
+            return Err(Error {
+                source: mask.into(),
+                inner: SimpleError::length_mismatch(span, bytes.len(), parsed.len()),
+            });
+        }
+
+        let bytes: Vec<_> = bytes
+            .iter()
+            .zip(parsed)
+            .map(|(&byte, exact)| {
+                if exact {
+                    PatternByte::exact(byte)
+                } else {
+                    PatternByte::WILDCARD
+                }
+            })
+            .collect();
+        Ok(Self::from_pattern_bytes(&bytes))
+    }
+
     /// Contruct a [`DynamicNeedle`] using raw bytes, in plain Rust.
     ///
     /// # Syntax
@@ -329,10 +1243,240 @@ impl DynamicNeedle {
     /// ```
     #[must_use]
     pub fn from_bytes(bytes: &[Option<u8>]) -> Self {
-        let pattern = DynamicPattern::from_bytes(bytes);
+        let bytes: Vec<_> = bytes.iter().map(|&x| PatternByte::from(x)).collect();
+        Self::from_pattern_bytes(&bytes)
+    }
+
+    /// Construct a [`DynamicNeedle`] using raw bytes and a bitmask, in plain Rust.
+    ///
+    /// # Syntax
+    /// Expects two arrays of `u8` with equal length, where:
+    /// * `word` holds the bytes to match
+    /// * `mask` holds which bits of the corresponding byte in `word` must match exactly (`1`), or may match fuzzily (`0`)
+    ///
+    /// # Panics
+    /// Panics if `word` and `mask` differ in length.
+    ///
+    /// # Example
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_masked(&[0x48, 0x8B, 0x05], &[0xFF, 0xFF, 0xC7]);
+    /// let haystack = [0x48, 0x8B, 0x0D, 0x48, 0x8B, 0x3D];
+    /// let matches: Vec<_> = needle.find_iter(&haystack).map(|x| x.start()).collect();
+    /// assert_eq!(matches, [0, 3]);
+    /// ```
+    #[must_use]
+    pub fn from_masked(word: &[u8], mask: &[u8]) -> Self {
+        assert_eq!(
+            word.len(),
+            mask.len(),
+            "the word and mask of a needle must have the same length"
+        );
+        let bytes: Vec<_> = word
+            .iter()
+            .zip(mask)
+            .map(|(&word, &mask)| PatternByte { word, mask })
+            .collect();
+        Self::from_pattern_bytes(&bytes)
+    }
+
+    /// Construct a [`DynamicNeedle`] using a YARA style hex string.
+    ///
+    /// # Syntax
+    /// Expects a sequence of `byte`, `jump`, `negation`, or `alternation`, optionally separated by whitespace, and optionally enclosed in braces, where:
+    /// * `byte` is exactly 2 hexadecimals (uppercase or lowercase), indicating an exact match
+    ///   * either hexadecimal may be replaced with a `?`, indicating a fuzzy match on only that nibble
+    /// * `jump` is one of the following, indicating a fuzzy match on a variable number of bytes:
+    ///   * `[n]`, for exactly `n` bytes
+    ///   * `[n-m]`, for anywhere from `n` to `m` bytes
+    ///   * `[n-]`, for at least `n` bytes
+    ///   * `[-m]`, for at most `m` bytes
+    ///   * `[-]`, for any number of bytes
+    ///   * `n` and `m` must be from 0 to 65535
+    /// * `negation` is a `~` followed by a `byte`, e.g. `~CC` or `~?0`, indicating a match on any byte except it
+    /// * `alternation` is one or more sequences separated by `|` and enclosed in parentheses, indicating a match on any one of the sequences
+    ///   * alternations may be nested at most 32 deep
+    ///
+    /// As in YARA, neither the pattern nor any sequence in an `alternation` may be empty or begin or end with a `jump`, and a `jump` within an `alternation` must have an upper bound.
+    ///
+    /// When a needle can match at the same position in several ways, shorter jumps are preferred over longer ones, and earlier alternatives are preferred over later ones.
+    ///
+    /// # Example
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_yara("{ 4D 5A [2-4] ( 90 | CC ) ?? }").unwrap();
+    /// let haystack = [0x4D, 0x5A, 0x00, 0x00, 0x00, 0xCC, 0x01, 0x4D, 0x5A];
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(matched.range(), 0..7);
+    /// ```
+    pub fn from_yara(pattern: &str) -> Result<Self, Error<'_>> {
+        let nodes = parsing::parse(parsing::yara_pattern(), pattern)?;
+        Ok(Self::from_nodes(&nodes))
+    }
+
+    /// Formats the needle as an Ida style pattern, which [`DynamicNeedle::from_ida`] parses back into an equivalent needle.
+    ///
+    /// The pattern is normalized, so it may differ from the one the needle was constructed from:
+    /// * each token is separated by a single space, and comments are removed
+    /// * a `byte` is written in uppercase, and a `wildcard` as a single `?`
+    /// * repetitions are expanded, as are gaps of a short fixed length
+    /// * a `named wildcard` which no other must equal is written as a `capture` of a `wildcard`
+    /// * an `alternation` which is the last alternative of another is merged into it
+    /// * an `alternation` of a single byte in each alternative is written as a `set`
+    /// * a `set` or `negation` is written as a `byte` if possible, or else as whichever is shorter
+    ///
+    /// Needles constructed from YARA style patterns are written with an `alternation` for each alternation of more than one alternative,
+    /// unless it's written as a `set`.
+    ///
+    /// # Example
+    /// ```
+    /// # use aob_common::DynamicNeedle;
+    /// let needle = DynamicNeedle::from_ida("48 8b 05 (disp: ?? ?? ?? ??) (00-ff) CC{2} ; comment").unwrap();
+    /// assert_eq!(needle.to_ida(), "48 8B 05 (disp: ? ? ? ?) ? CC CC");
+    /// assert_eq!(needle.to_string(), needle.to_ida());
+    ///
+    /// let needle = DynamicNeedle::from_masked(&[0x48, 0x8B, 0x05], &[0xFF, 0xFF, 0xC7]);
+    /// assert_eq!(needle.to_ida(), "48 8B (05|0D|15|1D|25|2D|35|3D)");
+    ///
+    /// let needle = DynamicNeedle::from_yara("{ 4D 5A [2-4] ( 90 | CC 90 ) }").unwrap();
+    /// assert_eq!(needle.to_ida(), "4D 5A [2-4] (90|CC 90)");
+    /// ```
+    #[must_use]
+    pub fn to_ida(&self) -> String {
+        self.needle_ref().to_ida()
+    }
+
+    /// Formats the needle as a code style pattern, i.e. a string of bytes paired with a mask, which [`DynamicNeedle::from_code_style`] parses back into an equivalent needle.
+    ///
+    /// Yields `None` if the needle can't be expressed as a code style pattern, i.e. if it has a `gap` of a variable length, an `alternation`, a `set`, a `negation`, a `named wildcard`, or a `typed value` with a range, or if any byte is only partially masked. Captures and cursors are dropped.
+    ///
+    /// # Example
+    /// ```
+    /// # use aob_common::DynamicNeedle;
+    /// let needle = DynamicNeedle::from_ida("48 8B 05 (? ? ? ?)").unwrap();
+    /// let (bytes, mask) = needle.to_code_style().unwrap();
+    /// assert_eq!(bytes, b"\x48\x8B\x05\x00\x00\x00\x00");
+    /// assert_eq!(mask, "xxx????");
+    ///
+    /// let needle = DynamicNeedle::from_ida("48 8B 0?").unwrap();
+    /// assert!(needle.to_code_style().is_none());
+    /// ```
+    #[must_use]
+    pub fn to_code_style(&self) -> Option<(Vec<u8>, String)> {
+        self.needle_ref().code_style()
+    }
+
+    /// Construct a [`DynamicNeedle`] using an array literal, as it would appear in C or Rust source.
+    ///
+    /// # Syntax
+    /// Expects a sequence of `byte` or `wildcard` separated by commas, optionally enclosed in braces or brackets, where:
+    /// * `byte` is `0x` followed by 1 or 2 hexadecimals (uppercase or lowercase), indicating an exact match
+    /// * `wildcard` is one or two `?` characters, indicating a fuzzy match
+    ///
+    /// A trailing comma is allowed, as is whitespace around each element.
+    ///
+    /// # Example
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_array_literal("{ 0x78, ?, 0xBC }").unwrap();
+    /// let haystack = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE];
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(&haystack[matched.start()..], [0x78, 0x9A, 0xBC, 0xDE]);
+    ///
+    /// let needle = DynamicNeedle::from_array_literal("[0x56, 0x78, ??,]").unwrap();
+    /// let matched = needle.find(&haystack).unwrap();
+    /// assert_eq!(matched.as_bytes(), [0x56, 0x78, 0x9A]);
+    /// ```
+    pub fn from_array_literal(pattern: &str) -> Result<Self, Error<'_>> {
+        let nodes = parsing::parse(parsing::array_literal(), pattern)?;
+        Ok(Self::from_nodes(&nodes))
+    }
+
+    /// Construct a [`DynamicNeedle`] using a string of bytes, where wildcards are escaped with a backslash.
+    ///
+    /// # Syntax
+    /// Expects a string of bytes, where:
+    /// * `\?` indicates a fuzzy match
+    /// * `\\` indicates an exact match on a single backslash
+    /// * any other byte indicates an exact match on itself
+    ///
+    /// A backslash followed by anything else is an error. Rust byte strings don't accept `\?` as an escape, so either escape the backslash as in `b"\\?"`, or use a raw byte string as in `br"\?"`.
+    ///
+    /// Errors are reported relative to the string of bytes, where each byte counts as a single character.
+    ///
+    /// # Example
+    /// ```
+    /// # use aob_common::{DynamicNeedle, Needle as _};
+    /// let needle = DynamicNeedle::from_byte_string(br"Error: \?\? not found").unwrap();
+    /// let haystack = b"...Error: 42 not found...";
+    /// let matched = needle.find(haystack).unwrap();
+    /// assert_eq!(matched.as_bytes(), b"Error: 42 not found");
+    ///
+    /// let needle = DynamicNeedle::from_byte_string(b"C:\\\\Windows\\\\").unwrap();
+    /// assert_eq!(needle.len(), 11);
+    ///
+    /// let error = DynamicNeedle::from_byte_string(br"\x").unwrap_err();
+    /// assert_eq!(error.span(), 1..2);
+    /// ```
+    pub fn from_byte_string(bytes: &[u8]) -> Result<Self, Error<'static>> {
+        // each byte maps to the character of the same value, so spans line up with positions in `bytes`
+        let source: String = bytes.iter().copied().map(char::from).collect();
+        let nodes = parsing::parse(parsing::byte_string(), &source).map_err(Error::into_owned)?;
+        Ok(Self::from_nodes(&nodes))
+    }
+
+    #[must_use]
+    fn from_pattern_bytes(bytes: &[PatternByte]) -> Self {
+        let nodes: Vec<_> = bytes.iter().copied().map(Node::Byte).collect();
+        Self::from_nodes(&nodes)
+    }
+
+    #[must_use]
+    pub(crate) fn from_nodes(nodes: &[Node]) -> Self {
+        Self::from_compiled(Compiled::from_nodes(nodes))
+    }
+
+    #[must_use]
+    fn from_compiled(compiled: Compiled) -> Self {
+        let pattern = DynamicPattern::from_bytes(&compiled.bytes);
+        let prefilter = match &compiled.program {
+            Some(program) => {
+                // SAFETY: the program was compiled alongside the pattern's buffers
+                let program = unsafe { ProgramRef::new(program.as_raw(), (&pattern).into()) };
+                match program.anchor_chunk() {
+                    Some(chunk) => CompiledPrefilter::from_bytes(chunk),
+                    None => CompiledPrefilter::from_length(compiled.len),
+                }
+            }
+            None => CompiledPrefilter::from_bytes((&pattern).into()),
+        };
         Self {
-            prefilter: CompiledPrefilter::from_bytes((&pattern).into()),
+            prefilter,
+            pattern,
+            program: compiled.program,
+            len: compiled.len,
+            capture_names: compiled.capture_names,
+            capture_offsets: compiled.capture_offsets,
+            backreferences: compiled.backreferences,
+            fields: compiled.fields,
+        }
+    }
+
+    #[must_use]
+    fn needle_ref(&self) -> NeedleRef<'_> {
+        let pattern = (&self.pattern).into();
+        // SAFETY: the program was compiled alongside the pattern's buffers
+        let program = self
+            .program
+            .as_ref()
+            .map(|program| unsafe { ProgramRef::new(program.as_raw(), pattern) });
+        NeedleRef {
             pattern,
+            program,
+            capture_names: CaptureNames::Dynamic(&self.capture_names),
+            capture_offsets: &self.capture_offsets,
+            backreferences: &self.backreferences,
+            fields: &self.fields,
         }
     }
 
@@ -354,6 +1498,36 @@ impl DynamicNeedle {
         (&self.prefilter).into()
     }
 
+    #[doc(hidden)]
+    #[must_use]
+    pub fn serialize_program(&self) -> Option<RawProgram<'_>> {
+        self.program.as_ref().map(Program::as_raw)
+    }
+
+    #[doc(hidden)]
+    #[must_use]
+    pub fn serialize_capture_names(&self) -> Vec<Option<&str>> {
+        self.capture_names.iter().map(Option::as_deref).collect()
+    }
+
+    #[doc(hidden)]
+    #[must_use]
+    pub fn serialize_capture_offsets(&self) -> &[usize] {
+        &self.capture_offsets
+    }
+
+    #[doc(hidden)]
+    #[must_use]
+    pub fn serialize_backreferences(&self) -> &[(usize, usize)] {
+        &self.backreferences
+    }
+
+    #[doc(hidden)]
+    #[must_use]
+    pub fn serialize_fields(&self) -> &[(usize, RawField)] {
+        &self.fields
+    }
+
     #[cfg(test)]
     #[must_use]
     pub(crate) fn prefilter(&self) -> &CompiledPrefilter {
@@ -361,6 +1535,27 @@ impl DynamicNeedle {
     }
 }
 
+impl Display for DynamicNeedle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_ida())
+    }
+}
+
+/// Parses an Ida style pattern, see [`DynamicNeedle::from_ida`].
+///
+/// ```
+/// # use aob_common::{DynamicNeedle, Needle as _};
+/// let needle: DynamicNeedle = "78 ? BC".parse().unwrap();
+/// assert_eq!(needle.len(), 3);
+/// ```
+impl FromStr for DynamicNeedle {
+    type Err = Error<'static>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_ida(s).map_err(Error::into_owned)
+    }
+}
+
 impl Sealed for DynamicNeedle {}
 
 impl Needle for DynamicNeedle {
@@ -368,16 +1563,15 @@ impl Needle for DynamicNeedle {
         &'needle self,
         haystack: &'haystack [u8],
     ) -> Find<'needle, 'haystack> {
-        Find {
-            prefilter: self.prefilter.clone(),
-            pattern: (&self.pattern).into(),
-            haystack,
-            last_offset: 0,
-        }
+        Find::new(self.prefilter, self.needle_ref(), self.len, haystack)
     }
 
     fn len(&self) -> usize {
-        self.pattern.len()
+        self.len
+    }
+
+    fn capture_index(&self, name: &str) -> Option<usize> {
+        CaptureNames::Dynamic(&self.capture_names).position(name)
     }
 }
 
@@ -386,12 +1580,18 @@ mod tests {
     use super::{
         DynamicNeedle,
         Needle as _,
+        Uniqueness,
+    };
+    use crate::{
+        testing,
+        Reason,
     };
 
     #[test]
     fn test_from_ida() {
         assert!(DynamicNeedle::from_ida("4_ 42 41 43 41 42 41 42 43").is_err());
         assert!(DynamicNeedle::from_ida("11 ??? 22").is_err());
+        assert!(DynamicNeedle::from_ida("11 ?x 22").is_err());
 
         macro_rules! test_success {
             ($pattern:literal, $length:literal) => {
@@ -411,5 +1611,872 @@ mod tests {
         test_success!("11 ? ? 22 ? 33 44 ?", 8);
         test_success!("aA Bb 1d", 3);
         test_success!("11 ? 33 ?? 55 ? ?? 88", 8);
+        test_success!("1? ?2 ?? 4? ? ?6", 6);
+    }
+
+    #[test]
+    fn test_from_code_style() {
+        let needle = DynamicNeedle::from_code_style(b"\x48\x8B\x05\x00", "xxx?").unwrap();
+        assert_eq!(needle.len(), 4);
+        let haystack = [0x90, 0x48, 0x8B, 0x05, 0xAB, 0x48, 0x8B, 0x05];
+        let matches: Vec<_> = needle.find_iter(&haystack).map(|x| x.start()).collect();
+        assert_eq!(matches, [1]);
+
+        let needle = DynamicNeedle::from_code_style(b"", "").unwrap();
+        assert_eq!(needle.len(), 0);
+
+        let error = DynamicNeedle::from_code_style(b"\x48\x8B\x05", "xx?x?").unwrap_err();
+        assert_eq!(error.span(), 3..5);
+        assert_eq!(
+            error.reason(),
+            &Reason::LengthMismatch {
+                expected: 3,
+                found: 5
+            }
+        );
+
+        let error = DynamicNeedle::from_code_style(b"\x48\x8B\x05", "x?").unwrap_err();
+        assert_eq!(error.span(), 2..2);
+        assert_eq!(
+            error.reason(),
+            &Reason::LengthMismatch {
+                expected: 3,
+                found: 2
+            }
+        );
+
+        let error = DynamicNeedle::from_code_style(b"\x48\x8B\x05", "x.x").unwrap_err();
+        assert_eq!(error.span(), 1..2);
+        assert_eq!(error.reason(), &Reason::InvalidMaskCharacter('.'));
+
+        let error = DynamicNeedle::from_code_style(b"\x48\x8B\x05", "xx\"").unwrap_err();
+        assert_eq!(error.span(), 2..3);
+        assert_eq!(error.reason(), &Reason::InvalidMaskCharacter('"'));
+    }
+
+    #[test]
+    fn test_from_masked() {
+        let haystack: Vec<u8> = (0..=u8::MAX).collect();
+        let needle = DynamicNeedle::from_masked(&[0x10, 0x20, 0x30], &[0xF8, 0xF8, 0xF8]);
+        let matches: Vec<_> = needle.find_iter(&haystack).map(|x| x.start()).collect();
+        assert_eq!(matches, []);
+
+        let needle = DynamicNeedle::from_masked(&[0x10, 0x11, 0x12], &[0xF8, 0xFF, 0xF8]);
+        let matches: Vec<_> = needle.find_iter(&haystack).map(|x| x.start()).collect();
+        assert_eq!(matches, [0x10]);
+
+        let needle = DynamicNeedle::from_masked(&[0x80, 0x00], &[0x80, 0x00]);
+        assert_eq!(needle.find_iter(&haystack).count(), 0x7F);
+
+        let needle = DynamicNeedle::from_masked(&[], &[]);
+        assert_eq!(needle.len(), 0);
+    }
+
+    #[test]
+    #[should_panic = "the word and mask of a needle must have the same length"]
+    fn test_from_masked_mismatched() {
+        let _ = DynamicNeedle::from_masked(&[0x11, 0x22], &[0xFF]);
+    }
+
+    #[test]
+    fn test_nibbles() {
+        let haystack: Vec<u8> = (0..=u8::MAX).cycle().take(0x1000).collect();
+        for (pattern, expected) in [
+            ("4? 5?", 16),
+            ("?0 ?1 ?2", 256),
+            ("1? ?2 ?? 4? ? ?6", 0),
+            ("?0 ?1 ?2 ?3 ?4 ?5 ?6 ?7 ?8 ?9 ?A ?B", 256),
+            ("A? ?1 A? ?3 A? ?5 A? ?7 A? ?9 A? ?B A? ?D A? ?F B? ?1", 16),
+            (
+                "?0 ?1 ?2 ?3 ?4 ?5 ?6 ?7 ?8 ?9 ?A ?B ?C ?D ?E ?F ?0 ?1 ?2 ?3 ?4 ?5 ?6 ?7 ?8 ?9 ?A ?B ?C ?D ?E ?F ?0 ?1",
+                254,
+            ),
+        ] {
+            let needle = DynamicNeedle::from_ida(pattern).unwrap();
+            assert_eq!(needle.find_iter(&haystack).count(), expected, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_from_yara() {
+        macro_rules! test_matches {
+            ($pattern:literal, $haystack:expr, $expected:expr) => {
+                let needle = DynamicNeedle::from_yara($pattern).unwrap();
+                let matches: Vec<_> = needle
+                    .find_iter(&$haystack)
+                    .map(|x| (x.start(), x.end()))
+                    .collect();
+                assert_eq!(matches, $expected, "\"{}\"", $pattern);
+
+                // alternations of sequences are spelled the same way in Ida patterns
+                let needle = DynamicNeedle::from_ida(&needle.to_ida()).unwrap();
+                let matches: Vec<_> = needle
+                    .find_iter(&$haystack)
+                    .map(|x| (x.start(), x.end()))
+                    .collect();
+                assert_eq!(matches, $expected, "\"{}\"", needle.to_ida());
+            };
+        }
+
+        test_matches!("11 [2] 22", [0x11, 0x00, 0x11, 0x22, 0x22], [(0, 4)]);
+        test_matches!(
+            "11 [1-3] 22",
+            [0x11, 0x00, 0x11, 0x22, 0x22],
+            [(0, 4), (2, 5)]
+        );
+        test_matches!(
+            "11 [-] 22",
+            [0x11, 0x00, 0x00, 0x22, 0x11, 0x22],
+            [(0, 4), (4, 6)]
+        );
+        test_matches!("11 [-1] 22", [0x11, 0x00, 0x00, 0x22, 0x11, 0x22], [(4, 6)]);
+        test_matches!(
+            "11 ( 22 | 33 44 ) 55",
+            [0x11, 0x22, 0x55, 0x11, 0x33, 0x44, 0x55, 0x11, 0x33, 0x55],
+            [(0, 3), (3, 7)]
+        );
+        test_matches!(
+            "( 11 | 22 ) ( 33 | 44 )",
+            [0x11, 0x44, 0x22, 0x33],
+            [(0, 2), (2, 4)]
+        );
+        test_matches!(
+            "( 11 [1-2] 22 | 33 ) 44",
+            [0x11, 0x00, 0x22, 0x44, 0x33, 0x44],
+            [(0, 4), (4, 6)]
+        );
+
+        let needle = DynamicNeedle::from_yara("{ 11 [2-4] 22 }").unwrap();
+        assert_eq!(needle.len(), 4);
+
+        let error = DynamicNeedle::from_yara("11 [4-2] 22").unwrap_err();
+        assert_eq!(error.span(), 3..8);
+        assert_eq!(error.reason(), &Reason::InvertedRange);
+
+        let error = DynamicNeedle::from_yara("{ 11 [18446744073709551615-] 22 }").unwrap_err();
+        assert_eq!(error.span(), 6..26);
+        assert_eq!(error.reason(), &Reason::Overflow);
+
+        // sequences must be non-empty, begin and end with a byte, and only have bounded jumps within alternations
+        for (pattern, span) in [
+            ("{}", 1..2),
+            ("", 0..0),
+            ("[1-2] 11", 0..5),
+            ("{ 11 [2-] }", 5..9),
+            ("( 11 | 22 33 ) [-]", 15..18),
+            ("11 ( [1] 22 | 33 )", 5..8),
+            ("11 ( 22 [1-] 33 | 44 )", 8..12),
+        ] {
+            let error = DynamicNeedle::from_yara(pattern).unwrap_err();
+            assert_eq!(error.span(), span, "{pattern}");
+            assert_eq!(error.reason(), &Reason::Unexpected, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_overlapping_alternatives() {
+        // each alternation can end in two places, so every way through them is only tried once from each position
+        let pattern = "(11|11 11) ".repeat(24) + "22";
+        let needle = DynamicNeedle::from_ida(&pattern).unwrap();
+        let mut haystack = vec![0x11; 48];
+        haystack.extend([0x33, 0x22]);
+        assert!(needle.find(&haystack).is_none());
+        haystack[48] = 0x22;
+        let starts: Vec<_> = needle.find_iter(&haystack).map(|x| x.start()).collect();
+        assert_eq!(starts, (0..=24).collect::<Vec<_>>());
+
+        let needle = DynamicNeedle::from_ida("(11|11 11) (11|11 11) (a: 11) 22").unwrap();
+        let matched = needle.find(&[0x11, 0x11, 0x11, 0x11, 0x22]).unwrap();
+        assert_eq!(matched.range(), 0..5);
+        assert_eq!(matched.capture(0).unwrap().range(), 3..4);
+    }
+
+    #[test]
+    fn test_from_array_literal() {
+        for pattern in [
+            "{ 0x48, 0x8B, ?, 0x05 }",
+            "[0x48,0x8b,??,0x5]",
+            "0x48, 0X8B, ?, 0x05,",
+            "\n{\n    0x48, 0x8B,\n    ?, 0x05,\n}\n",
+        ] {
+            let needle = DynamicNeedle::from_array_literal(pattern).unwrap();
+            assert_eq!(needle.to_ida(), "48 8B ? 05", "{pattern}");
+        }
+        assert_eq!(DynamicNeedle::from_array_literal("{}").unwrap().len(), 0);
+
+        for (pattern, span, reason) in [
+            ("{ 0x48, 0x8G }", 11..12, Reason::InvalidHexdigit('G')),
+            ("{ 0x48, 0x100 }", 8..13, Reason::Overflow),
+            ("{ 0x48, 0x }", 10..11, Reason::InvalidHexdigit(' ')),
+            ("{ 0x48 0x8B }", 7..8, Reason::Unexpected),
+            ("{ 0x48, 0x8B ]", 13..14, Reason::Unexpected),
+        ] {
+            let error = DynamicNeedle::from_array_literal(pattern).unwrap_err();
+            assert_eq!(error.span(), span, "{pattern}");
+            assert_eq!(error.reason(), &reason, "{pattern}");
+        }
+        assert!(DynamicNeedle::from_array_literal("{ 0x48,, 0x8B }").is_err());
+        assert!(DynamicNeedle::from_array_literal("{ 48, 8B }").is_err());
+    }
+
+    #[test]
+    fn test_from_byte_string() {
+        let needle = DynamicNeedle::from_byte_string(br"\\x\?\\?").unwrap();
+        assert_eq!(needle.to_ida(), "5C 78 ? 5C 3F");
+        let needle = DynamicNeedle::from_byte_string(b"\xFF\x00\\?").unwrap();
+        assert_eq!(needle.to_ida(), "FF 00 ?");
+        assert_eq!(DynamicNeedle::from_byte_string(b"").unwrap().len(), 0);
+
+        for (bytes, span) in [
+            (&br"abc\"[..], 4..4),
+            (br"\?\n", 3..4),
+            (b"\xFF\\\xFF", 2..3),
+        ] {
+            let error = DynamicNeedle::from_byte_string(bytes).unwrap_err();
+            assert_eq!(error.span(), span);
+            assert_eq!(error.reason(), &Reason::Unexpected);
+        }
+        let error = DynamicNeedle::from_byte_string(b"\xE9\\x").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "error while parsing token \"x\" in range [2, 3)"
+        );
+    }
+
+    #[test]
+    fn test_gaps() {
+        let needle = DynamicNeedle::from_ida("11 [1-3] 22 33").unwrap();
+        assert_eq!(needle.len(), 4);
+        let haystack = [0x11, 0x00, 0x22, 0x33, 0x11, 0x00, 0x00, 0x00, 0x22, 0x33];
+        let matches: Vec<_> = needle
+            .find_iter(&haystack)
+            .map(|x| (x.start(), x.end()))
+            .collect();
+        assert_eq!(matches, [(0, 4), (4, 10)]);
+
+        // the prefilter anchors on the trailing chunk, so check against a naive search
+        let haystack = testing::haystack(&[0xAA, 0xBB, 0xCC, 0xDD], 0x2000);
+        let expected: Vec<_> = (0..haystack.len())
+            .filter_map(|start| {
+                if haystack[start] != 0xAA {
+                    return None;
+                }
+                (0..=3).find_map(|gap| {
+                    let chunk = start + 1 + gap;
+                    (haystack.get(chunk..chunk + 3)? == [0xBB, 0xCC, 0xDD])
+                        .then_some((start, chunk + 3))
+                })
+            })
+            .collect();
+        assert!(!expected.is_empty());
+
+        let needle = DynamicNeedle::from_ida("AA [0-3] BB CC DD").unwrap();
+        let matches: Vec<_> = needle
+            .find_iter(&haystack)
+            .map(|x| (x.start(), x.end()))
+            .collect();
+        assert_eq!(matches, expected);
+
+        // failures after an unbounded gap are remembered between starts, unless they refer back to a byte before the gap
+        let naive = |is_match: &dyn Fn(usize, usize) -> bool| -> Vec<_> {
+            (0..haystack.len())
+                .filter_map(|start| {
+                    (start + 1..haystack.len().saturating_sub(2))
+                        .find(|&chunk| is_match(start, chunk))
+                        .map(|chunk| (start, chunk + 3))
+                })
+                .collect()
+        };
+        for (pattern, expected) in [
+            (
+                "AA [-] BB ? DD",
+                naive(&|start, chunk| {
+                    haystack[start] == 0xAA
+                        && haystack[chunk] == 0xBB
+                        && haystack[chunk + 2] == 0xDD
+                }),
+            ),
+            (
+                "?<x> [-] CC ? ?<x>",
+                naive(&|start, chunk| {
+                    haystack[chunk] == 0xCC && haystack[chunk + 2] == haystack[start]
+                }),
+            ),
+        ] {
+            let needle = DynamicNeedle::from_ida(pattern).unwrap();
+            let matches: Vec<_> = needle
+                .find_iter(&haystack)
+                .map(|x| (x.start(), x.end()))
+                .collect();
+            assert_eq!(matches, expected, "{pattern}");
+        }
+
+        // a gap which can never be followed is only searched past once
+        let needle = DynamicNeedle::from_ida("AA [-] DD DD").unwrap();
+        let haystack = vec![0xAA; 0x10000];
+        assert_eq!(needle.find_iter(&haystack).count(), 0);
+        assert_eq!(needle.rfind_iter(&haystack).count(), 0);
+
+        let error = DynamicNeedle::from_ida("11 [3-1] 22").unwrap_err();
+        assert_eq!(error.span(), 3..8);
+        assert_eq!(error.reason(), &Reason::InvertedRange);
+
+        // gaps are at most `u16::MAX` bytes, and long fixed gaps are skipped over rather than compared
+        for (pattern, span) in [
+            ("11 [18446744073709551615] 22", 4..24),
+            ("11 [100000000000] 22", 4..16),
+            ("11 [1-65536] 22", 6..11),
+        ] {
+            let error = DynamicNeedle::from_ida(pattern).unwrap_err();
+            assert_eq!(error.span(), span, "{pattern}");
+            assert_eq!(error.reason(), &Reason::Overflow, "{pattern}");
+        }
+        let needle = DynamicNeedle::from_ida("11 [65535] 22").unwrap();
+        assert_eq!(needle.len(), 65537);
+        let mut haystack = vec![0x11; 0x20000];
+        haystack[65536] = 0x22;
+        let matches: Vec<_> = needle.find_iter(&haystack).map(|x| x.start()).collect();
+        assert_eq!(matches, [0]);
+    }
+
+    #[test]
+    fn test_sets() {
+        let needle = DynamicNeedle::from_ida("(10-12) 20 (E8|E9)").unwrap();
+        assert_eq!(needle.len(), 3);
+        assert!(needle.serialize_program().is_some());
+        for byte in 0..=u8::MAX {
+            let expected = (0x10..=0x12).contains(&byte);
+            assert_eq!(
+                needle.find(&[byte, 0x20, 0xE9]).is_some(),
+                expected,
+                "{byte}"
+            );
+            assert!(needle.find(&[byte, 0x20, 0xEA]).is_none(), "{byte}");
+        }
+
+        // sets that can be expressed with a mask don't need to be checked separately
+        let needle = DynamicNeedle::from_ida("(00-1F) 20 (E8|E9)").unwrap();
+        assert!(needle.serialize_program().is_none());
+        for byte in 0..=u8::MAX {
+            let expected = byte < 0x20;
+            assert_eq!(
+                needle.find(&[byte, 0x20, 0xE8]).is_some(),
+                expected,
+                "{byte}"
+            );
+        }
+
+        // long enough to be compared with simd
+        let pattern = "00 01 02 03 04 05 06 07 08 09 0A 0B 0C 0D 0E 0F (10|20|30) 11 12 13 14 15 16 17 18 19 1A 1B 1C 1D 1E 1F 20 21";
+        let needle = DynamicNeedle::from_ida(pattern).unwrap();
+        let mut haystack: Vec<u8> = (0x00..0x22).collect();
+        for (byte, expected) in [(0x10, true), (0x20, true), (0x30, true), (0x00, false)] {
+            haystack[0x10] = byte;
+            assert_eq!(needle.find(&haystack).is_some(), expected, "{byte}");
+        }
+    }
+
+    #[test]
+    fn test_negation() {
+        let needle = DynamicNeedle::from_ida("!CC 90 !00").unwrap();
+        assert_eq!(needle.len(), 3);
+        for byte in 0..=u8::MAX {
+            assert_eq!(
+                needle.find(&[byte, 0x90, 0x01]).is_some(),
+                byte != 0xCC,
+                "{byte}"
+            );
+            assert_eq!(
+                needle.find(&[0x01, 0x90, byte]).is_some(),
+                byte != 0x00,
+                "{byte}"
+            );
+        }
+
+        let haystack = [0xCC, 0xCC, 0x48, 0x8B, 0xCC, 0x8B, 0xC3];
+        let matches: Vec<_> = DynamicNeedle::from_ida("!CC 8B")
+            .unwrap()
+            .find_iter(&haystack)
+            .map(|x| x.start())
+            .collect();
+        assert_eq!(matches, [2]);
+
+        let error = DynamicNeedle::from_ida("90 !??").unwrap_err();
+        assert_eq!(error.span(), 3..6);
+        assert_eq!(error.reason(), &Reason::EmptySet);
+    }
+
+    #[test]
+    fn test_captures() {
+        let needle = DynamicNeedle::from_ida("48 8B 05 (disp: ? ? ? ?) (?)").unwrap();
+        assert!(needle.serialize_program().is_none());
+        let haystack = [0x90, 0x48, 0x8B, 0x05, 0x11, 0x22, 0x33, 0x44, 0x55];
+        let matched = needle.find(&haystack).unwrap();
+        assert_eq!(matched.range(), 1..9);
+        assert_eq!(matched.captures_len(), 2);
+        assert_eq!(needle.capture_index("disp"), Some(0));
+        let disp = matched.capture(0).unwrap();
+        assert_eq!(disp.range(), 4..8);
+        assert_eq!(disp.as_bytes(), [0x11, 0x22, 0x33, 0x44]);
+        assert_eq!(matched.capture(1).unwrap().range(), 8..9);
+
+        // captures are recorded along the path that actually matched, not any abandoned along the way
+        let needle = DynamicNeedle::from_ida("11 (skipped: [0-2] (22)) 33").unwrap();
+        let haystack = [0x11, 0x22, 0x00, 0x22, 0x33];
+        let matched = needle.find(&haystack).unwrap();
+        assert_eq!(matched.range(), 0..5);
+        let captures: Vec<_> = matched.captures().map(|x| x.range()).collect();
+        assert_eq!(captures, [1..4, 3..4]);
+        assert_eq!(needle.capture_index("skipped"), Some(0));
+
+        // each match keeps its own captures, even once later matches have been found
+        let needle = DynamicNeedle::from_ida("11 [0-1] ((22)|(33)) (?) (?)").unwrap();
+        let haystack = [0x11, 0x22, 0x44, 0x55, 0x11, 0x00, 0x33, 0x66, 0x77];
+        let found: Vec<_> = needle.find_iter(&haystack).collect();
+        let captures: Vec<Vec<_>> = found
+            .iter()
+            .map(|x| x.captures().map(|x| x.range()).collect())
+            .collect();
+        assert_eq!(
+            captures,
+            [[1..2, 0..0, 2..3, 3..4], [4..4, 6..7, 7..8, 8..9]]
+        );
+        assert_eq!(found[1].capture(3).unwrap().as_bytes(), [0x77]);
+
+        let needle = DynamicNeedle::from_ida("11 [1-2] 22").unwrap();
+        let matched = needle.find(&[0x11, 0x00, 0x22]).unwrap();
+        assert_eq!(matched.captures_len(), 0);
+        assert!(matched.capture(0).is_none());
+        assert!(needle.capture_index("disp").is_none());
+
+        // every capture is kept within the match
+        let pattern = "(?) ".repeat(19) + "(last: ?)";
+        let needle = DynamicNeedle::from_ida(&pattern).unwrap();
+        let haystack: Vec<_> = (0..20).collect();
+        let matched = needle.find(&haystack).unwrap();
+        let captures: Vec<_> = matched.captures().map(|x| x.start()).collect();
+        assert_eq!(captures, (0..20).collect::<Vec<_>>());
+        assert_eq!(needle.capture_index("last"), Some(19));
+        let needle = DynamicNeedle::from_ida(&format!("[0-1] {pattern}")).unwrap();
+        let matched = needle.find(&haystack).unwrap();
+        assert_eq!(matched.capture(19).unwrap().as_bytes(), [19]);
+
+        for (pattern, span) in [
+            ("(a: 61) (a: 62)", 9..10),
+            ("(a: 61 (a: 62))", 8..9),
+            ("(a: ?) ?<a>", 9..10),
+            ("?<a> ((a: 62)|63)", 7..8),
+        ] {
+            let error = DynamicNeedle::from_ida(pattern).unwrap_err();
+            assert_eq!(error.span(), span, "{pattern}");
+            assert_eq!(error.reason(), &Reason::DuplicateName, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_cursor() {
+        let needle = DynamicNeedle::from_ida("48 8D 0D ? ? ? ? ^ E8").unwrap();
+        let haystack = [0x90, 0x48, 0x8D, 0x0D, 0x11, 0x22, 0x33, 0x44, 0xE8];
+        let matched = needle.find(&haystack).unwrap();
+        assert_eq!((matched.range(), matched.cursor()), (1..9, 8));
+
+        let needle = DynamicNeedle::from_ida("11 [0-2] (^ 22) 33").unwrap();
+        let haystack = [0x11, 0x22, 0x00, 0x22, 0x33, 0x11, 0x22, 0x33];
+        let cursors: Vec<_> = needle
+            .find_iter(&haystack)
+            .map(|x| (x.range(), x.cursor()))
+            .collect();
+        assert_eq!(cursors, [(0..5, 3), (5..8, 6)]);
+
+        let needle = DynamicNeedle::from_ida("11 [0-2] 22").unwrap();
+        let matched = needle.find(&[0x00, 0x11, 0x00, 0x22]).unwrap();
+        assert_eq!(matched.cursor(), 1);
+        let needle = DynamicNeedle::from_bytes(&[Some(0x11), None]);
+        let matched = needle.find(&[0x00, 0x11, 0x00]).unwrap();
+        assert_eq!(matched.cursor(), 1);
+
+        let error = DynamicNeedle::from_ida("^ 11 ^ 22").unwrap_err();
+        assert_eq!(error.span(), 5..6);
+        assert_eq!(error.reason(), &Reason::DuplicateCursor);
+    }
+
+    #[test]
+    fn test_repetition() {
+        let needle = DynamicNeedle::from_ida("E8 ?{4} CC{3}").unwrap();
+        assert_eq!(needle.len(), 8);
+        let haystack = [0xE8, 0x11, 0x22, 0x33, 0x44, 0xCC, 0xCC, 0xCC, 0xE8, 0xCC];
+        let matches: Vec<_> = needle
+            .find_iter(&haystack)
+            .map(|x| (x.start(), x.end()))
+            .collect();
+        assert_eq!(matches, [(0, 8)]);
+
+        let error = DynamicNeedle::from_ida("E8 ?{0}").unwrap_err();
+        assert_eq!(error.span(), 5..6);
+        assert_eq!(error.reason(), &Reason::EmptyRepetition);
+    }
+
+    #[test]
+    fn test_named_wildcards() {
+        let needle = DynamicNeedle::from_ida("?<a> ? ?<a> ?<b> ?<b>").unwrap();
+        assert!(needle.serialize_program().is_none());
+        assert_eq!(needle.len(), 5);
+        let haystack = [0x11, 0x22, 0x11, 0x33, 0x33, 0x22, 0x44, 0x44];
+        let matches: Vec<_> = needle.find_iter(&haystack).map(|x| x.start()).collect();
+        assert_eq!(matches, [0]);
+        for byte in 0..=u8::MAX {
+            assert_eq!(
+                needle.find(&[0x11, 0x00, byte, 0x22, 0x22]).is_some(),
+                byte == 0x11,
+                "{byte}"
+            );
+        }
+
+        // each name is a single capture of its first wildcard
+        let found = needle.find(&haystack).unwrap();
+        let captures: Vec<_> = found.captures().map(|x| x.start()).collect();
+        assert_eq!(captures, [0, 3]);
+        assert_eq!(needle.capture_index("b"), Some(1));
+        assert_eq!(found.capture(1).unwrap().as_bytes(), [0x33]);
+        let needle = DynamicNeedle::from_ida(&"?<a> ".repeat(9)).unwrap();
+        let found = needle.find(&[0x55; 9]).unwrap();
+        assert_eq!(found.captures_len(), 1);
+
+        // the check still holds across gaps, and a mismatch is retried with a longer gap
+        let needle = DynamicNeedle::from_ida("11 ?<x> [0-2] ?<x> 22").unwrap();
+        let haystack = [0x11, 0x33, 0x44, 0x22, 0x33, 0x22, 0x11, 0x55, 0x55, 0x22];
+        let matches: Vec<_> = needle
+            .find_iter(&haystack)
+            .map(|x| (x.start(), x.end()))
+            .collect();
+        assert_eq!(matches, [(0, 6), (6, 10)]);
+        let found = needle.find(&haystack).unwrap();
+        let captures: Vec<_> = found.captures().map(|x| x.start()).collect();
+        assert_eq!(captures, [1]);
+
+        // a wildcard within an alternation must equal the first outside of any alternation, even if it comes first
+        let needle = DynamicNeedle::from_ida("(?<a> 61|62) 63 ?<a>").unwrap();
+        assert!(needle.find(b" aca").is_none());
+        assert_eq!(needle.find(b" acb").map(|x| x.range()), None);
+        assert_eq!(needle.find(b" aca ac?").map(|x| x.range()), None);
+        assert_eq!(needle.find(b"?ac?").map(|x| x.range()), Some(0..4));
+        assert_eq!(needle.find(b" bcz").map(|x| x.range()), Some(1..4));
+        let needle = DynamicNeedle::from_ida("(?<a> ?<a> 61|62) ?<a>").unwrap();
+        assert!(needle.find(b"xyax").is_none());
+        assert_eq!(needle.find(b"xxax").map(|x| x.range()), Some(0..4));
+        let found = needle.find(b"xxax").unwrap();
+        assert_eq!(found.capture(0).unwrap().range(), 3..4);
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let needle =
+            DynamicNeedle::from_ida("u16(0x40) u16be(?) f32(-0.5) u32le(0x????12??)").unwrap();
+        assert_eq!(needle.len(), 12);
+        assert_eq!(needle.to_ida(), "40 00 ? ? 00 00 00 BF ? 12 ? ?");
+        let haystack = [
+            0x40, 0x00, 0x40, 0x00, 0xAB, 0xCD, 0x00, 0x00, 0x00, 0xBF, 0x34, 0x12, 0x00, 0x00,
+        ];
+        let matched = needle.find(&haystack).unwrap();
+        assert_eq!(matched.range(), 2..14);
+
+        let needle = DynamicNeedle::from_ida("u8(0x0001) u16be(0x00000012)").unwrap();
+        assert_eq!(needle.to_ida(), "01 00 12");
+
+        for (pattern, span, reason) in [
+            ("u8(256)", 3..6, Reason::Overflow),
+            ("i16be(0x12345)", 6..13, Reason::Overflow),
+            ("u32(1.5)", 4..7, Reason::Unexpected),
+            ("f32(1e39)", 4..8, Reason::Overflow),
+            ("f64(-1e309)", 4..10, Reason::Overflow),
+        ] {
+            let error = DynamicNeedle::from_ida(pattern).unwrap_err();
+            assert_eq!(error.span(), span, "{pattern}");
+            assert_eq!(error.reason(), &reason, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_ranges() {
+        let needle = DynamicNeedle::from_ida("CC u16le(0x40..=0x400) DD").unwrap();
+        assert!(needle.serialize_program().is_none());
+        assert_eq!(needle.len(), 4);
+        assert_eq!(needle.to_code_style(), None);
+        let haystack = [
+            0xCC, 0x3F, 0x00, 0xDD, 0xCC, 0x40, 0x00, 0xDD, 0xCC, 0x00, 0x04, 0xDD, 0xCC, 0x01,
+            0x04, 0xDD,
+        ];
+        let matches: Vec<_> = needle.find_iter(&haystack).map(|x| x.range()).collect();
+        assert_eq!(matches, [4..8, 8..12]);
+
+        let needle = DynamicNeedle::from_ida("CC [0-2] u16be(0x40..=0x400)").unwrap();
+        assert!(needle.serialize_program().is_some());
+        let haystack = [0xCC, 0xAA, 0x00, 0x40, 0xCC, 0x04, 0x01];
+        let matches: Vec<_> = needle
+            .find_iter(&haystack)
+            .map(|x| (x.start(), x.end()))
+            .collect();
+        assert_eq!(matches, [(0, 4)]);
+
+        let needle = DynamicNeedle::from_ida("f32(0.0..=1.0)").unwrap();
+        assert!(needle.find(&(-0.0f32).to_le_bytes()).is_some());
+        assert!(needle.find(&(-0.5f32).to_le_bytes()).is_none());
+
+        for (pattern, span, reason) in [
+            ("u8(5..=4)", 3..8, Reason::InvertedRange),
+            ("u8(1..0x100)", 6..11, Reason::Overflow),
+            ("u16(0x1?..)", 4..8, Reason::Unexpected),
+        ] {
+            let error = DynamicNeedle::from_ida(pattern).unwrap_err();
+            assert_eq!(error.span(), span, "{pattern}");
+            assert_eq!(error.reason(), &reason, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_to_ida() {
+        let haystack = testing::haystack(&[0x11, 0x22, 0x33, 0xCC], 0x2000);
+        let ranges = |needle: &DynamicNeedle| -> Vec<_> {
+            needle.find_iter(&haystack).map(|x| x.range()).collect()
+        };
+
+        for needle in [
+            DynamicNeedle::from_ida("11 ?2 (00-22) !CC (a: ?<x> [0-2] ?<x>) ^ ?{2}").unwrap(),
+            DynamicNeedle::from_ida("(11 22|33 [1-]) !(11|22) 3?").unwrap(),
+            DynamicNeedle::from_yara("{ 11 [1-3] ( 22 | CC 33 ) }").unwrap(),
+            DynamicNeedle::from_yara("{ 11 ( 33 | 22 | 2? ) }").unwrap(),
+            DynamicNeedle::from_masked(&[0x11, 0x22, 0x33], &[0xFF, 0xEE, 0xF7]),
+            DynamicNeedle::from_code_style(b"\x11\x00\x33", "x?x").unwrap(),
+            DynamicNeedle::from_ida(r#""\x11\"3" [0-1] "\xCC" ?"#).unwrap(),
+            DynamicNeedle::from_ida("11 [0-1] u16be(0x2233..=0x33CC) i8(..0)").unwrap(),
+        ] {
+            let pattern = needle.to_ida();
+            assert_eq!(needle.to_string(), pattern);
+            let reparsed: DynamicNeedle = pattern.parse().unwrap();
+            assert_eq!(reparsed.to_ida(), pattern);
+            assert_eq!(reparsed.len(), needle.len(), "{pattern}");
+            assert!(!ranges(&needle).is_empty(), "{pattern}");
+            assert_eq!(ranges(&reparsed), ranges(&needle), "{pattern}");
+        }
+
+        for (pattern, expected) in [
+            ("u16be(0x2233..) (a: 11 (b: 22)) ^ 33", None),
+            ("((^) 11) 22", None),
+            ("11 (a: [1-2] 22) (b: ?<x> (33 44|?<x> 55))", None),
+            ("(a: (11 22|33 44)) [1000] (b: 55) ^", None),
+            ("?<x> (z: ?) ?<x> ?<y>", Some("?<x> (z: ?) ?<x> (y: ?)")),
+            ("(?<a> 61|62) 63 ?<a> (33|?<a>)", None),
+            ("(?<a> ?<a> 61|62) (?<b>|11) (22|?<b>)", None),
+            ("11 [2] 22", Some("11 ? ? 22")),
+            ("(11 22|(33 44|55 66))", Some("(11 22|33 44|55 66)")),
+            ("(c1: (63|62))", Some("(c1: (62|63))")),
+            ("(62|62)", Some("62")),
+        ] {
+            let needle = DynamicNeedle::from_ida(pattern).unwrap();
+            let formatted = needle.to_ida();
+            assert_eq!(formatted, expected.unwrap_or(pattern));
+            let reparsed = DynamicNeedle::from_ida(&formatted).unwrap();
+            assert_eq!(reparsed.to_ida(), formatted, "{pattern}");
+        }
+
+        let error = "11 2_".parse::<DynamicNeedle>().unwrap_err();
+        assert_eq!(error.span(), 4..5);
+        assert_eq!(error.reason(), &Reason::InvalidHexdigit('_'));
+    }
+
+    #[test]
+    fn test_to_code_style() {
+        let needle = DynamicNeedle::from_code_style(b"\x48\x8B\xFF\x05", "xx?x").unwrap();
+        let (bytes, mask) = needle.to_code_style().unwrap();
+        assert_eq!((&bytes[..], &mask[..]), (&b"\x48\x8B\x00\x05"[..], "xx?x"));
+        let reparsed = DynamicNeedle::from_code_style(&bytes, &mask).unwrap();
+        assert_eq!(reparsed.to_ida(), needle.to_ida());
+
+        let needle = DynamicNeedle::from_ida("(11 ^ ?) [2] 22").unwrap();
+        assert_eq!(
+            needle.to_code_style(),
+            Some((vec![0x11, 0x00, 0x00, 0x00, 0x22], "x???x".into()))
+        );
+        assert_eq!(
+            DynamicNeedle::from_ida("").unwrap().to_code_style(),
+            Some((Vec::new(), String::new()))
+        );
+
+        for pattern in ["11 ?2", "11 (22|33)", "11 [1-2] 22", "?<a> ?<a>", "!CC"] {
+            let needle = DynamicNeedle::from_ida(pattern).unwrap();
+            assert!(needle.to_code_style().is_none(), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_rfind() {
+        let needle = DynamicNeedle::from_ida("11 [1-3] 22 33").unwrap();
+        let haystack = [0x11, 0x00, 0x22, 0x33, 0x11, 0x00, 0x00, 0x00, 0x22, 0x33];
+        let matches: Vec<_> = needle
+            .rfind_iter(&haystack)
+            .map(|x| (x.start(), x.end()))
+            .collect();
+        assert_eq!(matches, [(4, 10), (0, 4)]);
+        assert_eq!(needle.rfind(&haystack).map(|x| x.start()), Some(4));
+        assert!(needle.rfind(&haystack[..9]).is_some_and(|x| x.start() == 0));
+
+        let haystack = testing::haystack(&[0xAA, 0xBB, 0xCC, 0xDD], 0x800);
+        for pattern in [
+            "",
+            "?",
+            "AA",
+            "? BB ? AA",
+            "AA BB CC",
+            "AA [0-3] BB CC DD",
+            "AA [-] DD DD",
+            "(BB|CC) ? (BB|CC)",
+            "?<a> ? ?<a> ?<a>",
+            "u16le(..0xBBBB) ? CC",
+        ] {
+            let needle = DynamicNeedle::from_ida(pattern).unwrap();
+            let forwards: Vec<_> = needle.find_iter(&haystack).map(|x| x.range()).collect();
+            let mut backwards: Vec<_> = needle.rfind_iter(&haystack).map(|x| x.range()).collect();
+            backwards.reverse();
+            assert_eq!(backwards, forwards, "{pattern}");
+
+            // alternating between both ends meets in the middle
+            let mut iter = needle.find_iter(&haystack);
+            let mut front = Vec::new();
+            let mut back = Vec::new();
+            while let Some(found) = iter.next() {
+                front.push(found.range());
+                let Some(found) = iter.next_back() else { break };
+                back.push(found.range());
+            }
+            front.extend(back.into_iter().rev());
+            assert_eq!(front, forwards, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_find_iter_in() {
+        let needle = DynamicNeedle::from_ida("(11) [0-2] 22").unwrap();
+        let haystack = [0x11, 0x22, 0x11, 0x00, 0x00, 0x22, 0x11, 0x00, 0x22];
+        let matches: Vec<_> = needle
+            .find_iter_in(&haystack, 1..8)
+            .map(|x| (x.range(), x.capture(0).unwrap().range()))
+            .collect();
+        assert_eq!(matches, [(2..6, 2..3)]);
+        let matches: Vec<_> = needle
+            .find_iter_in(&haystack, 2..9)
+            .rev()
+            .map(|x| x.range())
+            .collect();
+        assert_eq!(matches, [6..9, 2..6]);
+        assert_eq!(needle.find_at(&haystack, 1).map(|x| x.range()), Some(2..6));
+        assert!(needle.find_at(&haystack, 7).is_none());
+        assert!(needle.find_at(&haystack, haystack.len()).is_none());
+        assert_eq!(needle.find_iter_in(&haystack, 3..3).count(), 0);
+
+        // every window finds the same matches as searching a slice of the haystack
+        let haystack: Vec<_> = (0u8..64)
+            .map(|x| [0x11, 0x22, 0x00][usize::from(x) % 3])
+            .collect();
+        for pattern in ["", "?", "22", "11 ? 11", "11 [0-3] 22", "11 [-] 00"] {
+            let needle = DynamicNeedle::from_ida(pattern).unwrap();
+            for (start, end) in [(0, 64), (1, 63), (5, 9), (30, 32), (64, 64)] {
+                let expected: Vec<_> = needle
+                    .find_iter(&haystack[start..end])
+                    .map(|x| x.start() + start..x.end() + start)
+                    .collect();
+                let matches: Vec<_> = needle
+                    .find_iter_in(&haystack, start..end)
+                    .map(|x| x.range())
+                    .collect();
+                assert_eq!(matches, expected, "{pattern} in {start}..{end}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_non_overlapping() {
+        let needle = DynamicNeedle::from_ida("11 [0-2] 11").unwrap();
+        let haystack = [0x11, 0x00, 0x11, 0x11, 0x00, 0x00, 0x11, 0x11];
+        let matches: Vec<_> = needle.find_iter(&haystack).map(|x| x.range()).collect();
+        assert_eq!(matches, [0..3, 2..4, 3..7, 6..8]);
+        let matches: Vec<_> = needle
+            .find_iter_non_overlapping(&haystack)
+            .map(|x| x.range())
+            .collect();
+        assert_eq!(matches, [0..3, 3..7]);
+
+        // empty matches still make progress
+        let needle = DynamicNeedle::from_ida("").unwrap();
+        let matches: Vec<_> = needle
+            .find_iter_non_overlapping(&haystack[..3])
+            .map(|x| x.range())
+            .collect();
+        assert_eq!(matches, [0..0, 1..1, 2..2, 3..3]);
+
+        let needle = DynamicNeedle::from_ida("? ?").unwrap();
+        assert_eq!(needle.find_iter_non_overlapping(&haystack).count(), 4);
+        assert_eq!(needle.find_iter_non_overlapping(&haystack[..7]).count(), 3);
+    }
+
+    #[test]
+    #[should_panic = "alignment must not be zero"]
+    fn test_aligned_zero() {
+        let needle = DynamicNeedle::from_ida("11").unwrap();
+        let _ = needle.find_iter_aligned(&[0x11], 0, 0);
+    }
+
+    #[test]
+    fn test_aligned() {
+        let haystack = testing::haystack(&[0xAA, 0xBB, 0xCC, 0xDD], 0x800);
+        for pattern in [
+            "",
+            "?",
+            "AA BB",
+            "? BB ? AA",
+            "AA [0-3] BB CC DD",
+            "AA [-] DD DD",
+            "(BB|CC) ? (BB|CC)",
+        ] {
+            let needle = DynamicNeedle::from_ida(pattern).unwrap();
+            for (alignment, remainder) in [(1, 0), (2, 1), (4, 0), (4, 3), (16, 5)] {
+                let expected: Vec<_> = needle
+                    .find_iter(&haystack)
+                    .map(|x| x.range())
+                    .filter(|x| x.start % alignment == remainder)
+                    .collect();
+                let matches: Vec<_> = needle
+                    .find_iter_aligned(&haystack, alignment, remainder)
+                    .map(|x| x.range())
+                    .collect();
+                assert_eq!(matches, expected, "{pattern} % {alignment} == {remainder}");
+
+                let mut matches: Vec<_> = needle
+                    .find_iter_aligned(&haystack, alignment, remainder)
+                    .rev()
+                    .map(|x| x.range())
+                    .collect();
+                matches.reverse();
+                assert_eq!(matches, expected, "{pattern} % {alignment} == {remainder}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_unique() {
+        let needle = DynamicNeedle::from_ida("90 90").unwrap();
+        assert!(matches!(
+            needle.find_unique(&[0x90, 0xC3]),
+            Uniqueness::NotFound
+        ));
+        assert!(matches!(
+            needle.find_unique(&[0xC3, 0x90, 0x90, 0xC3]),
+            Uniqueness::Unique(found) if found.range() == (1..3)
+        ));
+        // overlapping matches are ambiguous too
+        assert!(matches!(
+            needle.find_unique(&[0x90, 0x90, 0x90, 0xC3, 0x90, 0x90]),
+            Uniqueness::Ambiguous { first, second }
+                if first.range() == (0..2) && second.range() == (1..3)
+        ));
     }
 }
@@ -0,0 +1,295 @@
+use crate::{
+    needle::{
+        Find,
+        Match,
+        Needle,
+    },
+    prefilter::{
+        MultiIter,
+        MultiPrefilter,
+    },
+};
+use std::{
+    cmp::{
+        Ordering,
+        Reverse,
+    },
+    collections::BinaryHeap,
+};
+
+/// A collection of [`Needle`]s which can all be searched for in a single pass over a haystack.
+///
+/// Each needle is identified by its position in the order the set was built from.
+/// Needles of different types can be mixed by building the set from `&dyn Needle`s.
+///
+/// Only needles with a run of exact bytes a bounded distance from the start of every match share that single pass.
+/// Any other needle, such as one made only of wildcards or one whose exact bytes all follow an unbounded gap,
+/// is searched for on its own in a separate pass over the haystack.
+///
+/// Matches are held back until no needle could still find one which starts earlier, so a needle whose exact bytes
+/// lie far from the start of its matches delays the matches of every other needle in the set.
+///
+/// ```
+/// # use aob_common::{DynamicNeedle, Needle, NeedleSet};
+/// let cat = DynamicNeedle::from_ida("63 61 74").unwrap();
+/// let dog = DynamicNeedle::from_ida("64 ? 67").unwrap();
+/// let set = NeedleSet::new([&cat, &dog]);
+/// let found: Vec<_> = set
+///     .find_iter(b"a_dog_chased_a_cat")
+///     .map(|(id, found)| (id, found.range()))
+///     .collect();
+/// assert_eq!(found, [(1, 2..5), (0, 15..18)]);
+/// ```
+pub struct NeedleSet<'needles> {
+    /// The state needed to search for each needle, without a haystack.
+    finds: Vec<Find<'needles, 'static>>,
+    /// The offset of each needle's literal from the position its prefilter yields, and the greatest distance between that position
+    /// and the start of a match, or `None` if the needle is searched for on its own.
+    literals: Vec<Option<(usize, usize)>>,
+    prefilter: MultiPrefilter,
+    /// The greatest distance between the start of a match and the literal found by the prefilter.
+    reach: usize,
+}
+
+impl<'needles> NeedleSet<'needles> {
+    /// Builds a set from many needles, in the order of their ids.
+    #[must_use]
+    pub fn new<N>(needles: impl IntoIterator<Item = &'needles N>) -> Self
+    where
+        N: Needle + ?Sized + 'needles,
+    {
+        let finds: Vec<_> = needles
+            .into_iter()
+            .map(|needle| needle.find_iter(&[]))
+            .collect();
+        let found: Vec<_> = finds.iter().map(Find::literal).collect();
+        let literals: Vec<_> = found
+            .iter()
+            .map(|literal| {
+                let (literal, max_distance) = literal.as_ref()?;
+                Some((literal.offset, *max_distance))
+            })
+            .collect();
+        let prefilter = MultiPrefilter::new(
+            found
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, literal)| Some((index, literal?.0))),
+        );
+        let reach = literals
+            .iter()
+            .flatten()
+            .map(|(offset, max_distance)| offset + max_distance)
+            .max()
+            .unwrap_or_default();
+        Self {
+            finds,
+            literals,
+            prefilter,
+            reach,
+        }
+    }
+
+    /// The number of needles in the set.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.finds.len()
+    }
+
+    /// Whether the set has no needles at all.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.finds.is_empty()
+    }
+
+    /// Finds all matches of every needle, iteratively.
+    ///
+    /// Matches are yielded alongside the id of their needle, in order of where they start and then by id.
+    /// Each needle yields the same matches as [`Needle::find_iter`] would.
+    #[must_use]
+    pub fn find_iter<'set, 'haystack>(
+        &'set self,
+        haystack: &'haystack [u8],
+    ) -> FindSet<'set, 'haystack> {
+        let mut iter = FindSet {
+            set: self,
+            finds: self
+                .finds
+                .iter()
+                .map(|find| find.with_haystack(haystack))
+                .collect(),
+            candidates: Some(self.prefilter.find_iter(haystack)),
+            position: 0,
+            pending: BinaryHeap::new(),
+        };
+        for (index, literal) in self.literals.iter().enumerate() {
+            if literal.is_none() {
+                iter.push_next(index);
+            }
+        }
+        iter
+    }
+}
+
+/// A match which has been found, but not yet yielded.
+struct Pending<'haystack> {
+    index: usize,
+    found: Match<'haystack>,
+}
+
+impl Pending<'_> {
+    #[must_use]
+    fn order(&self) -> (usize, usize) {
+        (self.found.start(), self.index)
+    }
+}
+
+impl PartialEq for Pending<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.order() == other.order()
+    }
+}
+
+impl Eq for Pending<'_> {}
+
+impl PartialOrd for Pending<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order().cmp(&other.order())
+    }
+}
+
+/// An [`Iterator`] for finding subsequent matches of every needle in a [`NeedleSet`].
+pub struct FindSet<'set, 'haystack> {
+    set: &'set NeedleSet<'set>,
+    finds: Vec<Find<'set, 'haystack>>,
+    /// The needles whose literals were found, or `None` once the prefilter is exhausted.
+    candidates: Option<MultiIter<'haystack, 'set>>,
+    /// The position of the last literal found by the prefilter.
+    position: usize,
+    pending: BinaryHeap<Reverse<Pending<'haystack>>>,
+}
+
+impl FindSet<'_, '_> {
+    /// Finds the next match of a needle which is searched for on its own.
+    fn push_next(&mut self, index: usize) {
+        if let Some(found) = self.finds[index].next() {
+            self.pending.push(Reverse(Pending { index, found }));
+        }
+    }
+}
+
+impl<'haystack> Iterator for FindSet<'_, 'haystack> {
+    type Item = (usize, Match<'haystack>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // matches found later by the prefilter can't start before anything out of its reach
+            if let Some(Reverse(first)) = self.pending.peek() {
+                if self.candidates.is_none() || first.found.start() + self.set.reach < self.position
+                {
+                    let Reverse(Pending { index, found }) = self.pending.pop()?;
+                    if self.set.literals[index].is_none() {
+                        self.push_next(index);
+                    }
+                    return Some((index, found));
+                }
+            }
+
+            let candidates = self.candidates.as_mut()?;
+            let Some((index, anchor)) = candidates.next() else {
+                self.candidates = None;
+                continue;
+            };
+            let (offset, _) =
+                self.set.literals[index].expect("only needles with literals should be candidates");
+            self.position = anchor + offset;
+            while let Some(found) = self.finds[index].next_at_anchor(anchor) {
+                self.pending.push(Reverse(Pending { index, found }));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NeedleSet;
+    use crate::{
+        testing,
+        DynamicNeedle,
+        Needle,
+    };
+
+    /// Finds every match of each needle on its own, in the order a set should yield them.
+    fn find_each(needles: &[DynamicNeedle], haystack: &[u8]) -> Vec<(usize, usize, usize)> {
+        let mut found: Vec<_> = needles
+            .iter()
+            .enumerate()
+            .flat_map(|(id, needle)| {
+                needle
+                    .find_iter(haystack)
+                    .map(move |found| (found.start(), id, found.end()))
+            })
+            .collect();
+        found.sort_unstable();
+        found
+    }
+
+    #[test]
+    fn test_find_iter() {
+        let needles: Vec<_> = [
+            "61 62",
+            "64 61 ? 61",
+            "62 ? 61",
+            "? 63 63 ? 61",
+            "61 [1-3] 62 63",
+            "[0-2] 63 61",
+            "61 [-] 62 62",
+            "?",
+            "? ?",
+            "?<x> (61|63) ?<x>",
+            "u8(0x61..=0x62) 63",
+        ]
+        .iter()
+        .map(|pattern| DynamicNeedle::from_ida(pattern).unwrap())
+        .collect();
+
+        let haystack = testing::haystack(b"abcd", 2000);
+
+        for len in [0, 1, 2, 3, 7, 64, haystack.len()] {
+            let haystack = &haystack[..len];
+            let set = NeedleSet::new(&needles);
+            let found: Vec<_> = set
+                .find_iter(haystack)
+                .map(|(id, found)| (found.start(), id, found.end()))
+                .collect();
+            assert_eq!(found, find_each(&needles, haystack), "{len}");
+        }
+    }
+
+    #[test]
+    fn test_mixed() {
+        let first = DynamicNeedle::from_ida("11 22").unwrap();
+        let second = DynamicNeedle::from_yara("{ 22 [1-2] 44 }").unwrap();
+        let needles: [&dyn Needle; 2] = [&first, &second];
+        let set = NeedleSet::new(needles);
+        assert_eq!(set.len(), 2);
+        assert!(!set.is_empty());
+
+        let haystack = [0x11, 0x22, 0x33, 0x44, 0x11, 0x22, 0x44];
+        let found: Vec<_> = set
+            .find_iter(&haystack)
+            .map(|(id, found)| (id, found.range()))
+            .collect();
+        assert_eq!(found, [(0, 0..2), (1, 1..4), (0, 4..6)]);
+
+        let set = NeedleSet::new::<DynamicNeedle>([]);
+        assert!(set.is_empty());
+        assert_eq!(set.find_iter(&haystack).next().map(|(id, _)| id), None);
+    }
+}
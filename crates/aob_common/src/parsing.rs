@@ -1,40 +1,929 @@
-use crate::error::SimpleError;
+use crate::{
+    ast::{
+        self,
+        Node,
+    },
+    error::SimpleError,
+    field::{
+        to_pattern_bytes,
+        RawField,
+        RawScalar,
+    },
+    pattern::{
+        PatternByte,
+        RawByteSet,
+    },
+    Error,
+};
 use chumsky::{
+    combinator::Repeated,
     primitive::{
+        any,
         choice,
+        end,
         filter,
         filter_map,
         just,
+        one_of,
     },
+    text,
+    BoxedParser,
     Parser,
 };
+use std::{
+    collections::HashMap,
+    ops::Range,
+};
+
+/// Runs `parser` over the entirety of `source`, keeping only the first error on failure.
+pub(crate) fn parse<T>(
+    parser: impl Parser<char, T, Error = SimpleError>,
+    source: &str,
+) -> Result<T, Error<'_>> {
+    parser
+        .then_ignore(end())
+        .parse(source)
+        .map_err(|mut errors| {
+            let error = errors
+                .drain(..)
+                .next()
+                .expect("failure to parse should produce at least one error");
+            Error {
+                source: source.into(),
+                inner: error,
+            }
+        })
+}
+
+#[must_use]
+fn whitespace() -> impl Parser<char, (), Error = SimpleError> + Clone {
+    filter(|c: &char| c.is_whitespace()).repeated().ignored()
+}
+
+/// A `;` or `//` followed by anything up until the end of the line.
+#[must_use]
+fn comment() -> impl Parser<char, (), Error = SimpleError> + Clone {
+    choice((just(";"), just("//")))
+        .then(filter(|c: &char| *c != '\n').repeated())
+        .ignored()
+}
+
+/// Whitespace within an Ida style pattern, where comments may appear anywhere whitespace can.
+fn ida_whitespace() -> Repeated<impl Parser<char, (), Error = SimpleError> + Clone> {
+    filter(|c: &char| c.is_whitespace())
+        .ignored()
+        .or(comment())
+        .repeated()
+}
+
+/// A single hexadecimal, or a `?` to indicate a wildcard nibble.
+#[must_use]
+fn nibble() -> impl Parser<char, Option<u8>, Error = SimpleError> + Clone {
+    filter_map(|span, c: char| match c {
+        '0'..='9' => Ok(Some(c as u8 - b'0')),
+        'a'..='f' => Ok(Some(c as u8 - b'a' + 0xA)),
+        'A'..='F' => Ok(Some(c as u8 - b'A' + 0xA)),
+        '?' => Ok(None),
+        _ => Err(SimpleError::invalid_hexdigit(span, c)),
+    })
+}
+
+/// Exactly 2 hexadecimals, where either may be a `?` to indicate a wildcard nibble.
+#[must_use]
+fn byte() -> impl Parser<char, PatternByte, Error = SimpleError> + Clone {
+    nibble()
+        .then(nibble())
+        .map(|(high, low)| PatternByte::from_nibbles(high, low))
+}
 
+/// Exactly 2 hexadecimals, without any wildcards.
 #[must_use]
-pub(crate) fn ida_pattern() -> impl Parser<char, Vec<Option<u8>>, Error = SimpleError> {
-    let whitespace = filter(|c: &char| c.is_whitespace()).repeated();
-    let wildcard = just("?").repeated().at_least(1).at_most(2).to(None);
-    let byte = filter_map(|span, c: char| {
-        if c.is_ascii_hexdigit() {
-            Ok(c as u8)
+fn exact_byte() -> impl Parser<char, u8, Error = SimpleError> + Clone {
+    let nibble = filter_map(|span, c: char| match c {
+        '0'..='9' => Ok(c as u8 - b'0'),
+        'a'..='f' => Ok(c as u8 - b'a' + 0xA),
+        'A'..='F' => Ok(c as u8 - b'A' + 0xA),
+        _ => Err(SimpleError::invalid_hexdigit(span, c)),
+    });
+    nibble.then(nibble).map(|(high, low)| high << 4 | low)
+}
+
+/// One or more `byte`s or inclusive `first-last` ranges of bytes, separated by `|` and enclosed in parentheses,
+/// along with whether it is just a lone `byte`.
+#[must_use]
+fn set_or_lone_byte() -> impl Parser<char, (RawByteSet, bool), Error = SimpleError> + Clone {
+    let range = exact_byte()
+        .then_ignore(just('-').padded_by(ida_whitespace()))
+        .then(exact_byte())
+        .try_map(|(first, last), span| {
+            if first <= last {
+                let mut set = RawByteSet::EMPTY;
+                for byte in first..=last {
+                    set.insert(byte);
+                }
+                Ok((set, false))
+            } else {
+                Err(SimpleError::inverted_range(span))
+            }
+        });
+    let byte = byte().map(|byte| (RawByteSet::from(byte), true));
+
+    choice((range, byte))
+        .padded_by(ida_whitespace())
+        .separated_by(just('|'))
+        .at_least(1)
+        .delimited_by(just('('), just(')'))
+        .map(|sets| {
+            let lone = matches!(sets[..], [(_, true)]);
+            let set = sets
+                .into_iter()
+                .fold(RawByteSet::EMPTY, |union, (set, _)| union.union(set));
+            (set, lone)
+        })
+}
+
+/// One or more `byte`s or inclusive `first-last` ranges of bytes, separated by `|` and enclosed in parentheses.
+#[must_use]
+fn set() -> impl Parser<char, RawByteSet, Error = SimpleError> + Clone {
+    set_or_lone_byte().map(|(set, _)| set)
+}
+
+/// A non-negative decimal number.
+#[must_use]
+fn number() -> impl Parser<char, usize, Error = SimpleError> + Clone {
+    filter(char::is_ascii_digit)
+        .repeated()
+        .at_least(1)
+        .collect::<String>()
+        .try_map(|digits, span| digits.parse().map_err(|_| SimpleError::overflow(span)))
+}
+
+/// A bound of a gap, which is at most `u16::MAX` bytes.
+#[must_use]
+fn jump_bound() -> impl Parser<char, usize, Error = SimpleError> + Clone {
+    number().try_map(|len, span| {
+        if len > u16::MAX.into() {
+            Err(SimpleError::overflow(span))
         } else {
-            Err(SimpleError::invalid_hexdigit(span, c))
+            Ok(len)
         }
     })
+}
+
+/// A gap of `[n]`, `[n-m]`, `[n-]`, `[-m]`, or `[-]` bytes, where `whitespace` may pad either bound.
+#[must_use]
+fn jump<O>(
+    whitespace: impl Parser<char, O, Error = SimpleError> + Clone,
+) -> impl Parser<char, Node, Error = SimpleError> + Clone {
+    jump_bound()
+        .or_not()
+        .padded_by(whitespace.clone())
+        .then(
+            just('-')
+                .ignore_then(jump_bound().or_not().padded_by(whitespace))
+                .or_not(),
+        )
+        .delimited_by(just('['), just(']'))
+        .try_map(|(min, max), span| {
+            let (min, max) = match (min, max) {
+                (Some(len), None) => (len, Some(len)),
+                (min, Some(max)) => (min.unwrap_or(0), max),
+                (None, None) => return Err(SimpleError::unexpected(span)),
+            };
+            if max.is_some_and(|max| max < min) {
+                Err(SimpleError::inverted_range(span))
+            } else {
+                Ok(Node::Jump { min, max })
+            }
+        })
+}
+
+/// A `!` followed by a `byte` or a `set`, matching any byte except those.
+#[must_use]
+fn negation() -> impl Parser<char, RawByteSet, Error = SimpleError> + Clone {
+    just('!')
+        .ignore_then(choice((set(), byte().map(RawByteSet::from))))
+        .try_map(complement)
+}
+
+/// Every byte except those in `set`, which must not be all of them.
+fn complement(set: RawByteSet, span: Range<usize>) -> Result<RawByteSet, SimpleError> {
+    let set = set.complement();
+    if set.len() == 0 {
+        Err(SimpleError::empty_set(span))
+    } else {
+        Ok(set)
+    }
+}
+
+/// An escape sequence within a quoted string, of `\\`, `\"`, `\0`, `\n`, `\r`, or `\t`.
+#[must_use]
+fn escaped_char() -> impl Parser<char, char, Error = SimpleError> + Clone {
+    just('\\').ignore_then(choice((
+        just('\\'),
+        just('"'),
+        just('0').to('\0'),
+        just('n').to('\n'),
+        just('r').to('\r'),
+        just('t').to('\t'),
+    )))
+}
+
+/// A quoted string encoded as UTF-8, where `\xHH` is a raw byte, or encoded as UTF-16 when prefixed with `u` or `u16le` for little endian, or `u16be` for big endian.
+#[must_use]
+fn string() -> impl Parser<char, Vec<u8>, Error = SimpleError> + Clone {
+    let plain = filter(|c: &char| !matches!(c, '"' | '\\'));
+    let utf8 = choice((
+        just("\\x").ignore_then(exact_byte()).map(|byte| vec![byte]),
+        choice((escaped_char(), plain)).map(|c| c.to_string().into_bytes()),
+    ))
     .repeated()
-    .exactly(2)
-    .map(|digits| {
-        let digits = String::from_utf8(digits).unwrap();
-        Some(u8::from_str_radix(&digits, 16).unwrap())
-    });
+    .flatten()
+    .delimited_by(just('"'), just('"'));
+
+    let utf16 = choice((escaped_char(), plain))
+        .repeated()
+        .collect::<String>()
+        .delimited_by(just('"'), just('"'));
+    let big_endian = just("u16be")
+        .ignore_then(utf16.clone())
+        .map(|text| text.encode_utf16().flat_map(u16::to_be_bytes).collect());
+    let little_endian = choice((just("u16le"), just("u")))
+        .ignore_then(utf16)
+        .map(|text| text.encode_utf16().flat_map(u16::to_le_bytes).collect());
+
+    choice((utf8, big_endian, little_endian))
+}
+
+/// A number within a typed value, before it is encoded.
+#[derive(Clone)]
+enum Literal {
+    /// The digits of a `0x` prefixed hexadecimal, where `None` is a wildcard nibble.
+    Hex(Vec<Option<u8>>),
+    /// The text of a decimal number.
+    Decimal(String),
+}
+
+#[derive(Clone)]
+enum Value {
+    Wildcard,
+    Literal(Literal),
+    /// A range of `min..max` or `min..=max`, where either bound may be left out of the former.
+    Range {
+        min: Option<(Literal, Range<usize>)>,
+        max: Option<(Literal, Range<usize>)>,
+        inclusive: bool,
+    },
+}
 
-    choice((wildcard, byte))
-        .separated_by(whitespace.at_least(1))
+/// A type of `u`, `i`, or `f` followed by its size in bits and an optional `le` or `be` suffix, and then a value enclosed in parentheses.
+///
+/// The value may be a `?`, a `0x` prefixed hexadecimal where any digit may be a `?`, a decimal number, or a range between exact numbers,
+/// and is encoded as little endian unless the type has a `be` suffix.
+#[must_use]
+fn typed_value() -> impl Parser<char, Vec<Node>, Error = SimpleError> + Clone {
+    let scalar = choice((
+        just('u').to(RawScalar::Unsigned),
+        just('i').to(RawScalar::Signed),
+        just('f').to(RawScalar::Float),
+    ));
+    let endian = choice((just("le").to(false), just("be").to(true)));
+    let ty = scalar.then(number()).then(endian.or_not()).try_map(
+        |((scalar, bits), big_endian), span| match (scalar, bits) {
+            (RawScalar::Unsigned | RawScalar::Signed, 8 | 16 | 32 | 64)
+            | (RawScalar::Float, 32 | 64) => Ok((scalar, bits / 8, big_endian.unwrap_or(false))),
+            _ => Err(SimpleError::unexpected(span)),
+        },
+    );
+
+    let digits = || filter(char::is_ascii_digit).repeated().at_least(1);
+    let hex = choice((just("0x"), just("0X")))
+        .ignore_then(nibble().repeated().at_least(1))
+        .map(Literal::Hex);
+    let decimal = just('-')
+        .or_not()
+        .chain::<char, _, _>(digits())
+        .chain::<char, _, _>(
+            just('.')
+                .chain(digits())
+                .or_not()
+                .map(Option::unwrap_or_default),
+        )
+        .chain::<char, _, _>(
+            one_of("eE")
+                .chain(one_of("+-").or_not())
+                .chain::<char, _, _>(digits())
+                .or_not()
+                .map(Option::unwrap_or_default),
+        )
         .collect()
-        .padded_by(whitespace)
+        .map(Literal::Decimal);
+    let literal = choice((hex, decimal)).map_with_span(|literal, span| (literal, span));
+    let range = literal
+        .clone()
+        .or_not()
+        .then(choice((just("..=").to(true), just("..").to(false))))
+        .then(literal.clone().or_not())
+        .map(|((min, inclusive), max)| Value::Range {
+            min,
+            max,
+            inclusive,
+        });
+
+    let value = choice((
+        just('?').to(Value::Wildcard),
+        range,
+        literal.map(|(literal, _)| Value::Literal(literal)),
+    ))
+    .map_with_span(|value, span| (value, span))
+    .padded_by(ida_whitespace())
+    .delimited_by(just('('), just(')'));
+
+    // invalid values are reported without failing, so their error isn't lost when a byte like `f3` parses instead
+    ty.then(value).validate(|(ty, (value, span)), _, emit| {
+        typed_nodes(ty, value, span).unwrap_or_else(|error| {
+            emit(error);
+            Vec::new()
+        })
+    })
+}
+
+/// Encodes `value` as the nodes of a `(scalar, len, big_endian)` type.
+fn typed_nodes(
+    (scalar, len, big_endian): (RawScalar, usize, bool),
+    value: Value,
+    span: Range<usize>,
+) -> Result<Vec<Node>, SimpleError> {
+    let (word, mask) = match value {
+        Value::Wildcard => (0, 0),
+        Value::Literal(literal) => encode(scalar, len, literal, span)?,
+        Value::Range {
+            min,
+            max,
+            inclusive,
+        } => {
+            let field = field((scalar, len, big_endian), min, max, inclusive, span)?;
+            return Ok(vec![Node::Field(field)]);
+        }
+    };
+    Ok(to_pattern_bytes(word, mask, len, big_endian)
+        .into_iter()
+        .map(Node::Byte)
+        .collect())
+}
+
+/// Encodes `literal` as the bits of a `scalar` of `len` bytes, along with a mask of which bits must match.
+fn encode(
+    scalar: RawScalar,
+    len: usize,
+    literal: Literal,
+    span: Range<usize>,
+) -> Result<(u64, u64), SimpleError> {
+    let bits = len * 8;
+    let all = RawField::all_bits(len);
+    match literal {
+        Literal::Hex(digits) => {
+            // leading zeros don't change the value, so only the digits after them must fit
+            let zeros = digits.iter().take_while(|&&digit| digit == Some(0)).count();
+            if digits.len() - zeros > len * 2 {
+                return Err(SimpleError::overflow(span));
+            }
+            let (word, mask) = digits.into_iter().fold((0, all), |(word, mask), digit| {
+                let (digit, digit_mask) = digit.map_or((0, 0), |x| (x, 0xF));
+                (word << 4 | u64::from(digit), mask << 4 | digit_mask)
+            });
+            Ok((word, mask & all))
+        }
+        Literal::Decimal(text) => {
+            let word = match scalar {
+                // literals are always finite, unless they're too large for the type
+                RawScalar::Float if len == 4 => match text.parse::<f32>() {
+                    Ok(value) if !value.is_finite() => return Err(SimpleError::overflow(span)),
+                    word => word.map(|value| value.to_bits().into()),
+                },
+                RawScalar::Float => match text.parse::<f64>() {
+                    Ok(value) if !value.is_finite() => return Err(SimpleError::overflow(span)),
+                    word => word.map(f64::to_bits),
+                },
+                RawScalar::Unsigned | RawScalar::Signed => {
+                    let value: i128 = text
+                        .parse()
+                        .map_err(|_| SimpleError::unexpected(span.clone()))?;
+                    let (min, max) = match scalar {
+                        RawScalar::Signed => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+                        _ => (0, i128::from(all)),
+                    };
+                    if !(min..=max).contains(&value) {
+                        return Err(SimpleError::overflow(span));
+                    }
+                    // wraps negative values around to their two's complement
+                    Ok(u64::try_from(value.rem_euclid(1 << bits)).expect("value should fit"))
+                }
+            };
+            word.map(|word| (word, all))
+                .map_err(|_| SimpleError::unexpected(span))
+        }
+    }
+}
+
+/// Encodes the bounds of a range as a field of the given type, where a missing bound is the limit of the type.
+fn field(
+    (scalar, len, big_endian): (RawScalar, usize, bool),
+    min: Option<(Literal, Range<usize>)>,
+    max: Option<(Literal, Range<usize>)>,
+    inclusive: bool,
+    span: Range<usize>,
+) -> Result<RawField, SimpleError> {
+    let bound = |bound: Option<(Literal, Range<usize>)>| {
+        bound
+            .map(
+                |(literal, span)| match encode(scalar, len, literal, span.clone())? {
+                    // bounds must be exact
+                    (word, mask) if mask == RawField::all_bits(len) => Ok(word),
+                    _ => Err(SimpleError::unexpected(span)),
+                },
+            )
+            .transpose()
+    };
+    let min = bound(min)?.unwrap_or_else(|| RawField::lowest(scalar, len));
+    let max = match (bound(max)?, inclusive) {
+        (Some(max), true) => max,
+        (Some(max), false) => RawField::previous(scalar, len, max)
+            .ok_or_else(|| SimpleError::inverted_range(span.clone()))?,
+        (None, false) => RawField::highest(scalar, len),
+        (None, true) => return Err(SimpleError::unexpected(span)),
+    };
+    let field = RawField {
+        scalar,
+        len,
+        big_endian,
+        min,
+        max,
+    };
+    if field.is_empty() {
+        Err(SimpleError::inverted_range(span))
+    } else {
+        Ok(field)
+    }
+}
+
+/// A repetition count of `{n}`, where `n` is a decimal number from 1 to 65535.
+#[must_use]
+fn repetition() -> impl Parser<char, usize, Error = SimpleError> + Clone {
+    number()
+        .try_map(|count, span| {
+            if count == 0 {
+                Err(SimpleError::empty_repetition(span))
+            } else if count > u16::MAX.into() {
+                Err(SimpleError::overflow(span))
+            } else {
+                Ok(count)
+            }
+        })
+        .padded_by(ida_whitespace())
+        .delimited_by(just('{'), just('}'))
+}
+
+/// A token matching a single byte, optionally followed by a `repetition` of that token.
+#[must_use]
+fn repeatable(
+    token: impl Parser<char, Node, Error = SimpleError> + Clone,
+) -> impl Parser<char, Vec<Node>, Error = SimpleError> + Clone {
+    token
+        .then(repetition().or_not())
+        .map(|(node, count)| vec![node; count.unwrap_or(1)])
+}
+
+/// A name given to a capture or named wildcard, along with where it appears.
+#[derive(Clone, Debug)]
+struct Name {
+    text: String,
+    span: Range<usize>,
+    /// Whether the name is given to a named wildcard, which may share it with other named wildcards.
+    wildcard: bool,
+}
+
+/// The nodes of part of an Ida style pattern, along with every name given within it, in order.
+type Named = (Vec<Node>, Vec<Name>);
+
+/// The nodes of a single token of an Ida style pattern, along with every name given within it, and its span.
+type Token = (Vec<Node>, Vec<Name>, Range<usize>);
+
+/// Nodes which don't give any names.
+#[must_use]
+fn unnamed(nodes: Vec<Node>) -> Named {
+    (nodes, Vec::new())
+}
+
+/// Rejects the first token which brings the number of cursors in a sequence above one.
+fn at_most_one_cursor(tokens: Vec<Token>) -> Result<Named, SimpleError> {
+    let mut cursors = 0;
+    let mut result = (Vec::with_capacity(tokens.len()), Vec::new());
+    for (nodes, names, span) in tokens {
+        cursors += nodes.iter().map(Node::cursors).sum::<usize>();
+        if cursors > 1 {
+            return Err(SimpleError::duplicate_cursor(span));
+        }
+        result.0.extend(nodes);
+        result.1.extend(names);
+    }
+    Ok(result)
+}
+
+/// Rejects the first name which is already given to a capture, unless both are given to named wildcards.
+fn unique_names(names: Vec<Name>) -> Result<(), SimpleError> {
+    let mut seen = HashMap::new();
+    for Name {
+        text,
+        span,
+        wildcard,
+    } in names
+    {
+        if seen
+            .insert(text, wildcard)
+            .is_some_and(|other| !(other && wildcard))
+        {
+            return Err(SimpleError::duplicate_name(span));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects a pattern whose fewest number of bytes to match doesn't fit in a `usize`.
+fn bounded_len(nodes: Vec<Node>, span: Range<usize>) -> Result<Vec<Node>, SimpleError> {
+    match ast::sequence_min_len(&nodes) {
+        Some(_) => Ok(nodes),
+        None => Err(SimpleError::overflow(span)),
+    }
+}
+
+/// The deepest that parentheses may be nested within a pattern.
+const MAX_DEPTH: usize = 32;
+
+/// An opening parenthesis nested deeper than `MAX_DEPTH`, which consumes the rest of the input so that its error is the one reported.
+#[must_use]
+fn too_deep<O>() -> impl Parser<char, O, Error = SimpleError> + Clone {
+    just('(')
+        .map_with_span(|_, span| span)
+        .then_ignore(any().repeated())
+        .try_map(|span, _| Err(SimpleError::nested_too_deeply(span)))
+}
+
+/// Parentheses enclosing one or more sequences of `token` separated by `|`, which are a capture if there is only one sequence,
+/// or else an alternation.
+///
+/// The sequences are only parsed once, and then it's decided which of the two they are.
+#[must_use]
+fn ida_group(
+    token: BoxedParser<'static, char, Token, SimpleError>,
+) -> impl Parser<char, Named, Error = SimpleError> + Clone {
+    let sequence = token
+        .separated_by(ida_whitespace().at_least(1))
+        .at_least(1)
+        .try_map(|tokens, _| at_most_one_cursor(tokens))
+        .padded_by(ida_whitespace());
+    let name = text::ident()
+        .map_with_span(|text, span| Name {
+            text,
+            span,
+            wildcard: false,
+        })
+        .padded_by(ida_whitespace())
+        .then_ignore(just(':'));
+    just('(')
+        .ignore_then(name.or_not())
+        .then(sequence.separated_by(just('|')).at_least(1))
+        .then_ignore(just(')'))
+        .try_map(
+            |(name, mut alternatives), span| match (name, alternatives.len()) {
+                (name, 1) => {
+                    let (nodes, names) = alternatives.remove(0);
+                    let capture = Node::Capture {
+                        name: name.as_ref().map(|x| x.text.clone()),
+                        nodes,
+                    };
+                    Ok((vec![capture], name.into_iter().chain(names).collect()))
+                }
+                (None, _) => {
+                    let (alternatives, names): (Vec<_>, Vec<_>) = alternatives.into_iter().unzip();
+                    Ok((vec![Node::Alternation(alternatives)], names.concat()))
+                }
+                (Some(_), _) => Err(SimpleError::unexpected(span)),
+            },
+        )
+}
+
+#[must_use]
+pub(crate) fn ida_pattern() -> impl Parser<char, Vec<Node>, Error = SimpleError> {
+    let byte = repeatable(byte().map(Node::Byte));
+    let named_wildcard = just('?')
+        .ignore_then(
+            text::ident()
+                .map_with_span(|name, span| (name, span))
+                .delimited_by(just('<'), just('>')),
+        )
+        .then(repetition().or_not())
+        .map(|((name, span), count)| {
+            let nodes = vec![Node::NamedWildcard(name.clone()); count.unwrap_or(1)];
+            let name = Name {
+                text: name,
+                span,
+                wildcard: true,
+            };
+            (nodes, vec![name])
+        });
+    let wildcard = repeatable(just('?').to(Node::Byte(PatternByte::WILDCARD)));
+    let cursor = just('^').to(vec![Node::Cursor]);
+    let typed_value = typed_value();
+    let jump = jump(ida_whitespace()).map(|node| vec![node]);
+    let string = string().map(|bytes| {
+        bytes
+            .into_iter()
+            .map(|byte| Node::Byte(PatternByte::exact(byte)))
+            .collect()
+    });
+
+    // a lone byte in parentheses is a capture, so that only parenthesized sequences which can't be a set are a group
+    let set = set_or_lone_byte().try_map(|(set, lone), span| {
+        if lone {
+            Err(SimpleError::unexpected(span))
+        } else {
+            Ok(set)
+        }
+    });
+    let set = repeatable(choice((set, negation())).map(Node::Set));
+
+    // tried before bytes, so that e.g. `f32(1.5)` isn't mistaken for the byte `F3`
+    let leaf = choice((
+        typed_value.map(unnamed),
+        byte.map(unnamed),
+        named_wildcard,
+        wildcard.map(unnamed),
+        cursor.map(unnamed),
+        jump.map(unnamed),
+        string.map(unnamed),
+        set.map(unnamed),
+    ))
+    .boxed();
+
+    // each level of nesting gets its own parser, rather than recursing without limit
+    let mut token = leaf
+        .clone()
+        .or(too_deep())
+        .map_with_span(|(nodes, names), span| (nodes, names, span))
+        .boxed();
+    for _ in 0..MAX_DEPTH {
+        token = leaf
+            .clone()
+            .or(ida_group(token))
+            .map_with_span(|(nodes, names), span| (nodes, names, span))
+            .boxed();
+    }
+
+    token
+        .separated_by(ida_whitespace().at_least(1))
+        .try_map(|tokens, _| {
+            let (nodes, names) = at_most_one_cursor(tokens)?;
+            unique_names(names)?;
+            Ok(nodes)
+        })
+        .try_map(bounded_len)
+        .padded_by(ida_whitespace())
+}
+
+/// Rejects a YARA sequence which is empty, begins or ends with a jump, or has an unbounded jump within an alternation.
+fn yara_sequence(
+    tokens: Vec<(Node, Range<usize>)>,
+    span: Range<usize>,
+    in_alternation: bool,
+) -> Result<Vec<Node>, SimpleError> {
+    let last = tokens
+        .len()
+        .checked_sub(1)
+        .ok_or_else(|| SimpleError::unexpected(span))?;
+    let invalid = tokens.iter().enumerate().find(|(i, (node, _))| match node {
+        Node::Jump { max, .. } => *i == 0 || *i == last || (in_alternation && max.is_none()),
+        _ => false,
+    });
+    match invalid {
+        Some((_, (_, span))) => Err(SimpleError::unexpected(span.clone())),
+        None => Ok(tokens.into_iter().map(|(node, _)| node).collect()),
+    }
+}
+
+#[must_use]
+pub(crate) fn yara_pattern() -> impl Parser<char, Vec<Node>, Error = SimpleError> {
+    let negation = just('~')
+        .ignore_then(byte().map(RawByteSet::from))
+        .try_map(complement)
+        .map(Node::Set);
+    let leaf = choice((byte().map(Node::Byte), jump(whitespace()), negation)).boxed();
+
+    // each level of nesting gets its own parser, rather than recursing without limit
+    let mut token = leaf.clone().or(too_deep()).boxed();
+    for _ in 0..MAX_DEPTH {
+        let alternation = token
+            .map_with_span(|node, span| (node, span))
+            .padded_by(whitespace())
+            .repeated()
+            .at_least(1)
+            .try_map(|tokens, span| yara_sequence(tokens, span, true))
+            .separated_by(just('|'))
+            .at_least(1)
+            .delimited_by(just('('), just(')'))
+            .map(Node::Alternation);
+        token = leaf.clone().or(alternation).boxed();
+    }
+
+    // a sequence which stopped short of its end failed within a token, and that error is reported instead
+    let tokens = token
+        .map_with_span(|node, span| (node, span))
+        .padded_by(whitespace())
+        .repeated()
+        .then_ignore(end().or(just('}').ignored()).rewind())
+        .try_map(|tokens, span| yara_sequence(tokens, span, false))
+        .try_map(bounded_len);
+    choice((tokens.clone().delimited_by(just('{'), just('}')), tokens)).padded_by(whitespace())
+}
+
+/// Whether the mask character `c` matches exactly.
+///
+/// An invalid character is reported in place, rather than ending the mask where its end would then be expected.
+fn mask_character(c: char, span: Range<usize>, emit: &mut dyn FnMut(SimpleError)) -> bool {
+    match c {
+        'x' | 'X' => true,
+        '?' => false,
+        _ => {
+            emit(SimpleError::invalid_mask_character(span, c));
+            false
+        }
+    }
+}
+
+#[must_use]
+pub(crate) fn code_style_mask() -> impl Parser<char, Vec<bool>, Error = SimpleError> {
+    any().validate(mask_character).repeated()
+}
+
+/// A quoted string of bytes and a quoted mask separated by a comma, as they would appear in C or C++ source.
+#[must_use]
+pub(crate) fn code_style_pattern() -> impl Parser<char, Vec<Node>, Error = SimpleError> {
+    let escape = just("\\x").ignore_then(exact_byte());
+    let literal = filter(|c: &char| c.is_ascii() && !matches!(c, '"' | '\\')).map(|c| c as u8);
+    let bytes = choice((escape, literal))
+        .repeated()
+        .delimited_by(just('"'), just('"'));
+    let mask = filter(|c: &char| *c != '"')
+        .validate(mask_character)
+        .repeated()
+        .delimited_by(just('"'), just('"'))
+        .map_with_span(|mask, span| (mask, span));
+
+    bytes
+        .then_ignore(just(',').padded_by(whitespace()))
+        .then(mask)
+        .try_map(|(bytes, (mask, span)), _| {
+            // spans are relative to the mask, excluding its opening quote
+            let start = span.start + 1;
+            if bytes.len() == mask.len() {
+                Ok(bytes
+                    .into_iter()
+                    .zip(mask)
+                    .map(|(byte, exact)| {
+                        Node::Byte(if exact {
+                            PatternByte::exact(byte)
+                        } else {
+                            PatternByte::WILDCARD
+                        })
+                    })
+                    .collect())
+            } else {
+                let span = start + bytes.len().min(mask.len())..start + mask.len();
+                Err(SimpleError::length_mismatch(span, bytes.len(), mask.len()))
+            }
+        })
+        .padded_by(whitespace())
+}
+
+/// A string of bytes, each represented by the character of the same value, where `\?` is a wildcard and `\\` is a backslash.
+#[must_use]
+pub(crate) fn byte_string() -> impl Parser<char, Vec<Node>, Error = SimpleError> {
+    let escape = just('\\').ignore_then(choice((
+        just('?').to(PatternByte::WILDCARD),
+        just('\\').to(PatternByte::exact(b'\\')),
+    )));
+    let literal = filter_map(|span, c: char| match c {
+        '\\' => Err(SimpleError::unexpected(span)),
+        _ => u8::try_from(c)
+            .map(PatternByte::exact)
+            .map_err(|_| SimpleError::unexpected(span)),
+    });
+    choice((escape, literal)).map(Node::Byte).repeated()
+}
+
+/// A `0x` prefixed hexadecimal number which fits in a byte, as used by C or Rust.
+#[must_use]
+fn hex_literal() -> impl Parser<char, u8, Error = SimpleError> + Clone {
+    let first = filter_map(|span, c: char| {
+        c.to_digit(16)
+            .ok_or_else(|| SimpleError::invalid_hexdigit(span, c))
+    });
+    // later digits are checked in place, so that `0x8G` reports the `G` rather than a missing `,`
+    let rest = filter(char::is_ascii_alphanumeric).validate(|c, span, emit| {
+        c.to_digit(16).unwrap_or_else(|| {
+            emit(SimpleError::invalid_hexdigit(span, c));
+            0
+        })
+    });
+    just('0')
+        .ignore_then(one_of("xX"))
+        .ignore_then(first.chain(rest.repeated()))
+        .try_map(|digits, span| {
+            digits
+                .into_iter()
+                .try_fold(0u8, |value, digit| {
+                    value
+                        .checked_mul(0x10)?
+                        .checked_add(u8::try_from(digit).ok()?)
+                })
+                .ok_or_else(|| SimpleError::overflow(span))
+        })
+}
+
+/// A comma separated sequence of `0x` prefixed bytes or `?` wildcards, optionally enclosed in braces or brackets, as it would appear in C or Rust source.
+#[must_use]
+pub(crate) fn array_literal() -> impl Parser<char, Vec<Node>, Error = SimpleError> {
+    let wildcard = choice((just("??"), just("?"))).to(PatternByte::WILDCARD);
+    let elements = |min| {
+        choice((hex_literal().map(PatternByte::exact), wildcard))
+            .map(Node::Byte)
+            .padded_by(whitespace())
+            .separated_by(just(','))
+            .allow_trailing()
+            .at_least(min)
+            .padded_by(whitespace())
+    };
+    // without delimiters, an empty sequence must be the entire input, or else errors inside of delimiters would be hidden
+    choice((
+        elements(0).delimited_by(just('{'), just('}')),
+        elements(0).delimited_by(just('['), just(']')),
+        elements(1),
+        end().to(Vec::new()),
+    ))
+    .padded_by(whitespace())
+}
+
+/// Rejects the first byte which begins with a wildcard immediately after a byte which ends with one, since a run of wildcards without whitespace is ambiguous.
+fn separated_wildcards(tokens: Vec<(PatternByte, Range<usize>)>) -> Result<Vec<Node>, SimpleError> {
+    for ((first, first_span), (second, second_span)) in tokens.iter().zip(tokens.iter().skip(1)) {
+        // the low nibble of `first` and the high nibble of `second` are both wildcards
+        let wildcards = first.mask.trailing_zeros() >= 4 && second.mask.leading_zeros() >= 4;
+        if wildcards && first_span.end == second_span.start {
+            return Err(SimpleError::unexpected(second_span.clone()));
+        }
+    }
+    Ok(tokens
+        .into_iter()
+        .map(|(byte, _)| Node::Byte(byte))
+        .collect())
+}
+
+/// A sequence of `byte`s, optionally separated by whitespace, as used by x64dbg.
+#[must_use]
+pub(crate) fn x64dbg_pattern() -> impl Parser<char, Vec<Node>, Error = SimpleError> {
+    byte()
+        .map_with_span(|byte, span| (byte, span))
+        .padded_by(whitespace())
+        .repeated()
+        .try_map(|tokens, _| separated_wildcards(tokens))
+        .padded_by(whitespace())
+}
+
+/// A sequence of `byte`s or wildcards of `?`, `*`, or `**`, optionally separated by whitespace, as used by Cheat Engine.
+#[must_use]
+pub(crate) fn cheat_engine_pattern() -> impl Parser<char, Vec<Node>, Error = SimpleError> {
+    let wildcard = choice((just("**"), just("*"), just("?"))).to(PatternByte::WILDCARD);
+    choice((byte(), wildcard))
+        .map_with_span(|byte, span| (byte, span))
+        .padded_by(whitespace())
+        .repeated()
+        .try_map(|tokens, _| separated_wildcards(tokens))
+        .padded_by(whitespace())
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::{
+        ast::Node,
+        field::{
+            RawField,
+            RawScalar,
+        },
+        pattern::{
+            PatternByte,
+            RawByteSet,
+        },
+    };
     use chumsky::{
         primitive::end,
         Parser as _,
@@ -42,36 +931,699 @@ mod tests {
 
     #[test]
     fn test_success() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let expected = [
+            Node::Byte(PatternByte::exact(0xAA)),
+            Node::Byte(PatternByte::WILDCARD),
+            Node::Byte(PatternByte::exact(0xBB)),
+        ];
+        assert_eq!(parser.parse("AA ? BB").unwrap(), expected);
+        assert_eq!(parser.parse("AA ?? BB").unwrap(), expected);
+        assert_eq!(parser.parse("AA    ? BB").unwrap(), expected);
+        assert_eq!(parser.parse(" AA ? BB").unwrap(), expected);
+        assert_eq!(parser.parse("AA ? BB ").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_nibbles() {
         let parser = super::ida_pattern().then_ignore(end());
         assert_eq!(
-            parser.parse("AA ? BB").unwrap(),
-            [Some(0xAA), None, Some(0xBB)]
+            parser.parse("4? ?F a?").unwrap(),
+            [
+                Node::Byte(PatternByte {
+                    word: 0x40,
+                    mask: 0xF0
+                }),
+                Node::Byte(PatternByte {
+                    word: 0x0F,
+                    mask: 0x0F
+                }),
+                Node::Byte(PatternByte {
+                    word: 0xA0,
+                    mask: 0xF0
+                }),
+            ]
         );
+    }
+
+    #[test]
+    fn test_gaps() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let byte = |x| Node::Byte(PatternByte::exact(x));
         assert_eq!(
-            parser.parse("AA ?? BB").unwrap(),
-            [Some(0xAA), None, Some(0xBB)]
+            parser.parse("AA [2-4] BB [3] ? [ 0 - 1 ] CC").unwrap(),
+            [
+                byte(0xAA),
+                Node::Jump {
+                    min: 2,
+                    max: Some(4)
+                },
+                byte(0xBB),
+                Node::Jump {
+                    min: 3,
+                    max: Some(3)
+                },
+                Node::Byte(PatternByte::WILDCARD),
+                Node::Jump {
+                    min: 0,
+                    max: Some(1)
+                },
+                byte(0xCC),
+            ]
         );
+        assert!(parser.parse("AA [4-2] BB").is_err());
+        assert!(parser.parse("AA[2-4] BB").is_err());
+        assert!(parser.parse("AA [2-4 BB").is_err());
+        assert!(parser.parse("AA [65536-] BB").is_err());
+
+        let jump = Node::Jump {
+            min: usize::MAX,
+            max: None,
+        };
+        let error = super::bounded_len(vec![jump, byte(0xAA)], 0..5).unwrap_err();
+        assert_eq!(error.span, 0..5);
+        assert_eq!(error.reason, crate::Reason::Overflow);
+    }
+
+    #[test]
+    fn test_error() {
+        let parser = super::ida_pattern().then_ignore(end());
+        assert!(parser.parse("A ? BB").is_err());
+        assert!(parser.parse("AAA ? BB").is_err());
+        assert!(parser.parse("AA ??? BB").is_err());
+        assert!(parser.parse("AA 4?? BB").is_err());
+        assert!(parser.parse("AA ?4? BB").is_err());
+        assert!(parser.parse("Ax ? BB").is_err());
+        assert!(parser.parse("\"AA ? BB").is_err());
+    }
+
+    #[test]
+    fn test_sets() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let set = |bytes: &[u8]| {
+            let mut set = RawByteSet::EMPTY;
+            for &byte in bytes {
+                set.insert(byte);
+            }
+            Node::Set(set)
+        };
+
         assert_eq!(
-            parser.parse("AA    ? BB").unwrap(),
-            [Some(0xAA), None, Some(0xBB)]
+            parser.parse("AA (E8|E9) ( 00 - 03 | 7F | 8? )").unwrap(),
+            [
+                Node::Byte(PatternByte::exact(0xAA)),
+                set(&[0xE8, 0xE9]),
+                set(&[
+                    0x00, 0x01, 0x02, 0x03, 0x7F, 0x80, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+                    0x88, 0x89, 0x8A, 0x8B, 0x8C, 0x8D, 0x8E, 0x8F,
+                ]),
+            ]
         );
+        assert_eq!(parser.parse("(CC|CC)").unwrap(), [set(&[0xCC])]);
+
+        assert!(parser.parse("AA () BB").is_err());
+        assert!(parser.parse("AA (E8|) BB").is_err());
+        assert!(parser.parse("AA (1F-00) BB").is_err());
+        assert!(parser.parse("AA (0?-1F) BB").is_err());
+        assert!(parser.parse("AA (E8|E9 BB").is_err());
+        assert!(parser.parse("AA(E8|E9) BB").is_err());
+    }
+
+    #[test]
+    fn test_negation() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let negated = |bytes: &[u8]| {
+            let mut set = RawByteSet::EMPTY;
+            for byte in (0..=u8::MAX).filter(|x| !bytes.contains(x)) {
+                set.insert(byte);
+            }
+            Node::Set(set)
+        };
+
         assert_eq!(
-            parser.parse(" AA ? BB").unwrap(),
-            [Some(0xAA), None, Some(0xBB)]
+            parser.parse("!CC ? !00").unwrap(),
+            [
+                negated(&[0xCC]),
+                Node::Byte(PatternByte::WILDCARD),
+                negated(&[0x00])
+            ]
         );
         assert_eq!(
-            parser.parse("AA ? BB ").unwrap(),
-            [Some(0xAA), None, Some(0xBB)]
+            parser.parse("!(E8|E9) !F?").unwrap(),
+            [
+                negated(&[0xE8, 0xE9]),
+                negated(&(0xF0..=0xFF).collect::<Vec<_>>()),
+            ]
+        );
+
+        assert!(parser.parse("! CC").is_err());
+        assert!(parser.parse("!!CC").is_err());
+        assert!(parser.parse("!C").is_err());
+        assert!(parser.parse("!?").is_err());
+        assert_eq!(
+            super::parse(super::ida_pattern(), "AA !??")
+                .unwrap_err()
+                .reason(),
+            &crate::Reason::EmptySet
         );
     }
 
     #[test]
-    fn test_error() {
+    fn test_captures() {
         let parser = super::ida_pattern().then_ignore(end());
-        assert!(parser.parse("A ? BB").is_err());
-        assert!(parser.parse("AAA ? BB").is_err());
-        assert!(parser.parse("AA ??? BB").is_err());
-        assert!(parser.parse("Ax ? BB").is_err());
-        assert!(parser.parse("\"AA ? BB\"").is_err());
+        let byte = |x| Node::Byte(PatternByte::exact(x));
+        let wildcard = || Node::Byte(PatternByte::WILDCARD);
+
+        assert_eq!(
+            parser.parse("48 8B 05 (disp: ? ? ? ?)").unwrap(),
+            [
+                byte(0x48),
+                byte(0x8B),
+                byte(0x05),
+                Node::Capture {
+                    name: Some("disp".into()),
+                    nodes: vec![wildcard(), wildcard(), wildcard(), wildcard()],
+                },
+            ]
+        );
+        assert_eq!(
+            parser.parse("( AA (_x1 : BB) [1-2] ) (CC)").unwrap(),
+            [
+                Node::Capture {
+                    name: None,
+                    nodes: vec![
+                        byte(0xAA),
+                        Node::Capture {
+                            name: Some("_x1".into()),
+                            nodes: vec![byte(0xBB)],
+                        },
+                        Node::Jump {
+                            min: 1,
+                            max: Some(2),
+                        },
+                    ],
+                },
+                Node::Capture {
+                    name: None,
+                    nodes: vec![byte(0xCC)],
+                },
+            ]
+        );
+        // names which look like bytes are still names
+        assert_eq!(
+            parser.parse("(AB: CD)").unwrap(),
+            [Node::Capture {
+                name: Some("AB".into()),
+                nodes: vec![byte(0xCD)],
+            }]
+        );
+
+        assert!(parser.parse("(disp:)").is_err());
+        assert!(parser.parse("(1x: AA)").is_err());
+        assert!(parser.parse("(AA BB").is_err());
+        assert!(parser.parse("AA(BB)").is_err());
+    }
+
+    #[test]
+    fn test_cursor() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let byte = |x| Node::Byte(PatternByte::exact(x));
+
+        assert_eq!(
+            parser.parse("AA ^ BB").unwrap(),
+            [byte(0xAA), Node::Cursor, byte(0xBB)]
+        );
+        assert!(parser.parse("AA ^BB").is_err());
+
+        for (pattern, span) in [
+            ("^ AA ^", 5..6),
+            ("AA ^ (BB ^ ^)", 11..12),
+            ("^ (AA ^)", 2..8),
+        ] {
+            let error = super::parse(super::ida_pattern(), pattern).unwrap_err();
+            assert_eq!(error.reason(), &crate::Reason::DuplicateCursor, "{pattern}");
+            assert_eq!(error.span(), span, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_named_wildcards() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let named = |x: &str| Node::NamedWildcard(x.into());
+
+        assert_eq!(
+            parser.parse("?<a> 8B ?<reg_1>{2} ?<a>").unwrap(),
+            [
+                named("a"),
+                Node::Byte(PatternByte::exact(0x8B)),
+                named("reg_1"),
+                named("reg_1"),
+                named("a"),
+            ]
+        );
+        assert_eq!(
+            parser.parse("?A ??").unwrap(),
+            [
+                Node::Byte(PatternByte {
+                    word: 0x0A,
+                    mask: 0x0F
+                }),
+                Node::Byte(PatternByte::WILDCARD),
+            ]
+        );
+
+        assert!(parser.parse("?<>").is_err());
+        assert!(parser.parse("?<1>").is_err());
+        assert!(parser.parse("?<a b>").is_err());
+        assert!(parser.parse("?<a").is_err());
+        assert!(parser.parse("? <a>").is_err());
+    }
+
+    #[test]
+    fn test_repetition() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let byte = |x| Node::Byte(PatternByte::exact(x));
+        let wildcard = || Node::Byte(PatternByte::WILDCARD);
+
+        assert_eq!(
+            parser.parse("AA ?{3} CC{2} ??{ 1 }").unwrap(),
+            [
+                byte(0xAA),
+                wildcard(),
+                wildcard(),
+                wildcard(),
+                byte(0xCC),
+                byte(0xCC),
+                wildcard(),
+            ]
+        );
+        assert_eq!(parser.parse("!CC{2} (E8|E9){2}").unwrap().len(), 4);
+        assert_eq!(parser.parse("? {2}").unwrap_err()[0].span, 2..3);
+        assert!(parser.parse("^{2}").is_err());
+        assert!(parser.parse("[1]{2}").is_err());
+        assert!(parser.parse("CC{}").is_err());
+        assert!(parser.parse("CC{-1}").is_err());
+
+        for (pattern, span, reason) in [
+            ("AA CC{0}", 6..7, crate::Reason::EmptyRepetition),
+            ("?{65536}", 2..7, crate::Reason::Overflow),
+            ("?{99999999999999999999999}", 2..25, crate::Reason::Overflow),
+        ] {
+            let error = super::parse(super::ida_pattern(), pattern).unwrap_err();
+            assert_eq!(error.reason(), &reason, "{pattern}");
+            assert_eq!(error.span(), span, "{pattern}");
+        }
+        assert_eq!(parser.parse("?{65535}").unwrap().len(), 65535);
+    }
+
+    #[test]
+    fn test_alternation() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let byte = |x| Node::Byte(PatternByte::exact(x));
+
+        assert_eq!(
+            parser.parse("11 (22 33 | 44|( 55 ) ?) 66").unwrap(),
+            [
+                byte(0x11),
+                Node::Alternation(vec![
+                    vec![byte(0x22), byte(0x33)],
+                    vec![byte(0x44)],
+                    vec![
+                        Node::Capture {
+                            name: None,
+                            nodes: vec![byte(0x55)],
+                        },
+                        Node::Byte(PatternByte::WILDCARD),
+                    ],
+                ]),
+                byte(0x66),
+            ]
+        );
+        // alternatives of single bytes are still a set
+        assert_eq!(
+            parser.parse("(11|22)").unwrap(),
+            [Node::Set(
+                RawByteSet::from(PatternByte::exact(0x11))
+                    .union(RawByteSet::from(PatternByte::exact(0x22)))
+            )]
+        );
+
+        assert!(parser.parse("(11 22|)").is_err());
+        assert!(parser.parse("(11 22|33").is_err());
+        assert!(parser.parse("(^ 11|^ 22)").is_err());
+        assert!(parser.parse("(a: 11|22 33)").is_err());
+
+        // parentheses may only be nested so deep, and sets don't count towards that
+        let depth = super::MAX_DEPTH;
+        let nested =
+            |inner: &str, depth| (0..depth).fold(inner.to_owned(), |x, _| format!("({x}|33)"));
+        assert!(parser.parse(nested("11 22", depth)).is_ok());
+        assert!(parser
+            .parse(format!("({})", nested("11 22", depth)))
+            .is_err());
+        for depth in [depth + 1, 1000] {
+            let pattern = nested("11 22", depth);
+            let error = super::parse(super::ida_pattern(), &pattern).unwrap_err();
+            assert_eq!(error.reason(), &crate::Reason::NestedTooDeeply);
+            assert_eq!(error.span(), 32..33);
+        }
+
+        // each alternation is only parsed once, rather than again for every way it could be read
+        assert!(parser.parse(nested("(11|22)", depth)).is_ok());
+    }
+
+    #[test]
+    fn test_strings() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let bytes = |bytes: &[u8]| -> Vec<_> {
+            bytes
+                .iter()
+                .map(|&x| Node::Byte(PatternByte::exact(x)))
+                .collect()
+        };
+
+        assert_eq!(
+            parser.parse(r#"48 "MZ" 00"#).unwrap(),
+            bytes(&[0x48, b'M', b'Z', 0x00])
+        );
+        assert_eq!(
+            parser.parse(r#""a\"b\\c\0\n\r\t\x90é""#).unwrap(),
+            bytes(b"a\"b\\c\0\n\r\t\x90\xC3\xA9")
+        );
+        assert_eq!(
+            parser.parse(r#"u"Hi" u16le"é" u16be"Hi""#).unwrap(),
+            bytes(b"H\0i\0\xE9\0\0H\0i")
+        );
+        assert_eq!(
+            parser.parse(r#"u"😀""#).unwrap(),
+            bytes(&[0x3D, 0xD8, 0x00, 0xDE])
+        );
+        assert_eq!(parser.parse(r#"AA "" BB"#).unwrap(), bytes(&[0xAA, 0xBB]));
+        assert_eq!(
+            parser.parse(r#"("ab"|u"c")"#).unwrap(),
+            [Node::Alternation(vec![bytes(b"ab"), bytes(b"c\0")])]
+        );
+
+        assert!(parser.parse(r#""abc"#).is_err());
+        assert!(parser.parse(r#""a\qb""#).is_err());
+        assert!(parser.parse(r#"u"\x90""#).is_err());
+        assert!(parser.parse(r#""\x9""#).is_err());
+        assert!(parser.parse(r#"AA"MZ""#).is_err());
+        assert!(parser.parse(r#"u8"MZ""#).is_err());
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let bytes = |bytes: &[u8]| -> Vec<_> {
+            bytes
+                .iter()
+                .map(|&x| Node::Byte(PatternByte::exact(x)))
+                .collect()
+        };
+
+        for (pattern, expected) in [
+            ("u32le(0xDEADBEEF)", &[0xEF, 0xBE, 0xAD, 0xDE][..]),
+            ("u32be(0xDEADBEEF)", &[0xDE, 0xAD, 0xBE, 0xEF]),
+            ("u32(0xDEADBEEF)", &[0xEF, 0xBE, 0xAD, 0xDE]),
+            ("u16( 0x1 )", &[0x01, 0x00]),
+            ("u8(255) i8(-128)", &[0xFF, 0x80]),
+            ("i16be(-2)", &[0xFF, 0xFE]),
+            ("i64le(-1)", &[0xFF; 8]),
+            ("u64be(18446744073709551615)", &[0xFF; 8]),
+            ("f32(1.5)", &[0x00, 0x00, 0xC0, 0x3F]),
+            ("f64be(-2)", &[0xC0, 0, 0, 0, 0, 0, 0, 0]),
+            ("f32le(1e-1)", &[0xCD, 0xCC, 0xCC, 0x3D]),
+            ("f3 F3", &[0xF3, 0xF3]),
+        ] {
+            assert_eq!(parser.parse(pattern).unwrap(), bytes(expected), "{pattern}");
+        }
+
+        assert_eq!(
+            parser.parse("u16(?) u32le(0x12??34?6)").unwrap(),
+            [
+                Node::Byte(PatternByte::WILDCARD),
+                Node::Byte(PatternByte::WILDCARD),
+                Node::Byte(PatternByte {
+                    word: 0x06,
+                    mask: 0x0F
+                }),
+                Node::Byte(PatternByte::exact(0x34)),
+                Node::Byte(PatternByte::WILDCARD),
+                Node::Byte(PatternByte::exact(0x12)),
+            ]
+        );
+
+        for pattern in [
+            "u8(256)",
+            "u8(-1)",
+            "i8(128)",
+            "u16(0x12345)",
+            "u24(1)",
+            "f16(1)",
+            "f32(abc)",
+            "u32(1.5)",
+            "u32(0x)",
+            "f32(1.5",
+            "u32le 1",
+        ] {
+            assert!(parser.parse(pattern).is_err(), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_ranges() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let field = |scalar, len, big_endian, min, max| {
+            Node::Field(RawField {
+                scalar,
+                len,
+                big_endian,
+                min,
+                max,
+            })
+        };
+
+        assert_eq!(
+            parser
+                .parse("u32le(0x1000..0x2000) i16be( -2..=3 ) u8(..0x10) u64(1..)")
+                .unwrap(),
+            [
+                field(RawScalar::Unsigned, 4, false, 0x1000, 0x1FFF),
+                field(RawScalar::Signed, 2, true, 0xFFFE, 0x0003),
+                field(RawScalar::Unsigned, 1, false, 0x00, 0x0F),
+                field(RawScalar::Unsigned, 8, false, 1, u64::MAX),
+            ]
+        );
+        assert_eq!(
+            parser.parse("f32(-1.5..=2) i8(..)").unwrap(),
+            [
+                field(
+                    RawScalar::Float,
+                    4,
+                    false,
+                    (-1.5f32).to_bits().into(),
+                    2.0f32.to_bits().into()
+                ),
+                field(RawScalar::Signed, 1, false, 0x80, 0x7F),
+            ]
+        );
+
+        for pattern in [
+            "u8(5..5)",
+            "u8(5..=4)",
+            "u8(..0)",
+            "u8(1..=)",
+            "u8(0x1?..0x20)",
+            "u8(1..256)",
+            "u8(1...2)",
+            "u8(1..2..3)",
+        ] {
+            assert!(parser.parse(pattern).is_err(), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_comments() {
+        let parser = super::ida_pattern().then_ignore(end());
+        let byte = |x| Node::Byte(PatternByte::exact(x));
+        let expected = [
+            byte(0x48),
+            byte(0x8B),
+            byte(0x05),
+            Node::Byte(PatternByte::WILDCARD),
+        ];
+
+        assert_eq!(parser.parse("48 8B ; mov\n05 ?").unwrap(), expected);
+        assert_eq!(parser.parse("48 8B// mov\r\n05 ? //").unwrap(), expected);
+        assert_eq!(
+            parser
+                .parse("; leading\n\t48 8B 05 ; trailing\n\t? ;; doubled\n")
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            parser.parse("(disp: ; comment\n ? )").unwrap(),
+            [Node::Capture {
+                name: Some("disp".into()),
+                nodes: vec![Node::Byte(PatternByte::WILDCARD)],
+            }]
+        );
+        assert_eq!(
+            parser.parse("(E8 ; call\n | E9)").unwrap(),
+            parser.parse("(E8|E9)").unwrap()
+        );
+        assert_eq!(
+            parser.parse("[1 // min\n -4]").unwrap(),
+            [Node::Jump {
+                min: 1,
+                max: Some(4)
+            }]
+        );
+        assert_eq!(
+            parser.parse("u16( // value\n 0x1234 )").unwrap(),
+            [byte(0x34), byte(0x12)]
+        );
+        assert_eq!(
+            parser.parse("CC{ 2 ; twice\n}").unwrap(),
+            [byte(0xCC), byte(0xCC)]
+        );
+        assert_eq!(parser.parse("; 48 8B 05").unwrap(), []);
+
+        assert!(parser.parse("48 / 8B").is_err());
+        assert!(parser.parse("48 ; mov 05").is_ok_and(|x| x.len() == 1));
+
+        // spans still refer to the original text
+        let error = super::parse(super::ida_pattern(), "48 ; 8B\n05 ; x\n3_").unwrap_err();
+        assert_eq!(error.span(), 16..17);
+        assert_eq!(error.reason(), &crate::Reason::InvalidHexdigit('_'));
+    }
+
+    #[test]
+    fn test_yara() {
+        let parser = super::yara_pattern().then_ignore(end());
+        let byte = |x| Node::Byte(PatternByte::exact(x));
+        let jump = |min, max| Node::Jump { min, max };
+
+        let expected = [byte(0xAA), Node::Byte(PatternByte::WILDCARD), byte(0xBB)];
+        assert_eq!(parser.parse("{ AA ?? BB }").unwrap(), expected);
+        assert_eq!(parser.parse("AA??BB").unwrap(), expected);
+        assert_eq!(parser.parse(" { AA ?? BB } ").unwrap(), expected);
+
+        assert_eq!(
+            parser.parse("AA [4] [2-6] [ 3 - ] [-] [-5] BB").unwrap(),
+            [
+                byte(0xAA),
+                jump(4, Some(4)),
+                jump(2, Some(6)),
+                jump(3, None),
+                jump(0, None),
+                jump(0, Some(5)),
+                byte(0xBB),
+            ]
+        );
+
+        assert_eq!(
+            parser.parse("AA ( BB | CC DD | ( EE | FF ) )").unwrap(),
+            [
+                byte(0xAA),
+                Node::Alternation(vec![
+                    vec![byte(0xBB)],
+                    vec![byte(0xCC), byte(0xDD)],
+                    vec![Node::Alternation(vec![vec![byte(0xEE)], vec![byte(0xFF)]])],
+                ]),
+            ]
+        );
+
+        assert_eq!(
+            parser.parse("~CC ~?0").unwrap(),
+            [
+                Node::Set(RawByteSet::from(PatternByte::exact(0xCC)).complement()),
+                Node::Set(RawByteSet::from(PatternByte::from_nibbles(None, Some(0))).complement()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_yara_error() {
+        let parser = super::yara_pattern().then_ignore(end());
+        assert!(parser.parse("AA [] BB").is_err());
+        assert!(parser.parse("AA [6-2] BB").is_err());
+        assert!(parser.parse("AA [99999999999999999999999] BB").is_err());
+        assert!(parser.parse("AA [2-4 BB").is_err());
+        assert!(parser.parse("AA ( BB | ) CC").is_err());
+        assert!(parser.parse("AA ( ) CC").is_err());
+        assert!(parser.parse("{ AA BB").is_err());
+        assert!(parser.parse("AA B BB").is_err());
+        assert!(parser.parse("~??").is_err());
+        assert!(parser.parse("~ CC").is_err());
+
+        let nested = |depth| "( 11 ".repeat(depth) + "22" + &" )".repeat(depth);
+        assert!(parser.parse(nested(super::MAX_DEPTH)).is_ok());
+        for depth in [super::MAX_DEPTH + 1, 1000] {
+            let pattern = nested(depth);
+            let error = super::parse(super::yara_pattern(), &pattern).unwrap_err();
+            assert_eq!(error.reason(), &crate::Reason::NestedTooDeeply);
+            assert_eq!(error.span(), 160..161);
+        }
+    }
+
+    #[test]
+    fn test_dialects() {
+        let byte = |x| Node::Byte(PatternByte::exact(x));
+        let nibble = Node::Byte(PatternByte::from_nibbles(Some(4), None));
+        let wildcard = || Node::Byte(PatternByte::WILDCARD);
+
+        let parser = super::x64dbg_pattern().then_ignore(end());
+        let expected = [byte(0x48), nibble.clone(), wildcard()];
+        assert_eq!(parser.parse("48 4? ??").unwrap(), expected);
+        assert_eq!(parser.parse(" 484? ??\n").unwrap(), expected);
+        assert!(parser.parse("484???").is_err());
+        assert!(parser.parse("").unwrap().is_empty());
+        assert!(parser.parse("48 ? 4?").is_err());
+        assert!(parser.parse("48 *").is_err());
+
+        let parser = super::cheat_engine_pattern().then_ignore(end());
+        let expected = [
+            byte(0x48),
+            nibble,
+            wildcard(),
+            wildcard(),
+            wildcard(),
+            wildcard(),
+        ];
+        assert_eq!(parser.parse("48 4? ?? ? * **").unwrap(), expected);
+        assert!(parser.parse("48 4? ?? ? ***").is_err());
+        assert!(parser.parse("48 [1]").is_err());
+
+        let parser = super::code_style_pattern().then_ignore(end());
+        assert_eq!(
+            parser.parse(r#""\x48MZ\x00", "xxX?""#).unwrap(),
+            [byte(0x48), byte(b'M'), byte(b'Z'), wildcard()]
+        );
+        assert_eq!(parser.parse(r#" "" ,"" "#).unwrap(), []);
+        assert!(parser.parse(r#""\x48" "x""#).is_err());
+        assert!(parser.parse(r#""\x4", "x""#).is_err());
+        assert!(parser.parse(r#""\n", "x""#).is_err());
+        assert!(parser.parse(r#""\x48", "xx""#).is_err());
+
+        let parser = super::array_literal().then_ignore(end());
+        let expected = [byte(0x48), wildcard(), byte(0x05)];
+        assert_eq!(parser.parse("{ 0x48, ?, 0x5 }").unwrap(), expected);
+        assert_eq!(parser.parse("[0x48,??,0x05,]").unwrap(), expected);
+        assert_eq!(parser.parse(" 0x48 , ? , 0x05 ").unwrap(), expected);
+        assert_eq!(parser.parse(" ").unwrap(), []);
+        assert!(parser.parse("{ 0x48, ?, 0x05 ]").is_err());
+        assert!(parser.parse("{ 0x48, 4?, 0x05 }").is_err());
+        assert!(parser.parse(",").is_err());
+    }
+
+    #[test]
+    fn test_code_style_mask() {
+        let parser = super::code_style_mask().then_ignore(end());
+        assert_eq!(parser.parse("xX?x").unwrap(), [true, true, false, true]);
+        assert_eq!(parser.parse("").unwrap(), []);
+        assert!(parser.parse("xx ?").is_err());
+        assert!(parser.parse("x.x").is_err());
     }
 }
@@ -39,7 +39,7 @@ trait Simd: Clone + Copy + Sized {
     type Integer: Integer;
 
     #[must_use]
-    unsafe fn blendv_epi8(a: Self, b: Self, mask: Self) -> Self;
+    unsafe fn andnot_si(a: Self, b: Self) -> Self;
     #[must_use]
     unsafe fn cmpeq_epi8(a: Self, b: Self) -> Self;
     #[must_use]
@@ -49,7 +49,9 @@ trait Simd: Clone + Copy + Sized {
     #[must_use]
     unsafe fn movemask_epi8(a: Self) -> Self::Integer;
     #[must_use]
-    unsafe fn set1_epi8(a: u8) -> Self;
+    unsafe fn setzero_si() -> Self;
+    #[must_use]
+    unsafe fn xor_si(a: Self, b: Self) -> Self;
 }
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
@@ -61,20 +63,12 @@ mod sse2 {
     use std::arch::x86_64 as arch;
     use std::ptr::NonNull;
 
-    // https://github.com/aklomp/missing-sse-intrinsics
-    unsafe fn _mm_blendv_si128(a: __m128i, b: __m128i, mask: __m128i) -> __m128i {
-        arch::_mm_or_si128(
-            arch::_mm_andnot_si128(mask, a),
-            arch::_mm_and_si128(mask, b),
-        )
-    }
-
     impl super::Simd for __m128i {
         const LANE_COUNT: usize = 16;
         type Integer = u16;
 
-        unsafe fn blendv_epi8(a: Self, b: Self, mask: Self) -> Self {
-            _mm_blendv_si128(a, b, arch::_mm_cmplt_epi8(mask, arch::_mm_setzero_si128()))
+        unsafe fn andnot_si(a: Self, b: Self) -> Self {
+            arch::_mm_andnot_si128(a, b)
         }
 
         unsafe fn cmpeq_epi8(a: Self, b: Self) -> Self {
@@ -94,9 +88,12 @@ mod sse2 {
             arch::_mm_movemask_epi8(a) as u32 as u16
         }
 
-        #[expect(clippy::cast_possible_wrap)]
-        unsafe fn set1_epi8(a: u8) -> Self {
-            arch::_mm_set1_epi8(a as i8)
+        unsafe fn setzero_si() -> Self {
+            arch::_mm_setzero_si128()
+        }
+
+        unsafe fn xor_si(a: Self, b: Self) -> Self {
+            arch::_mm_xor_si128(a, b)
         }
     }
 }
@@ -114,8 +111,8 @@ mod avx2 {
         const LANE_COUNT: usize = 32;
         type Integer = u32;
 
-        unsafe fn blendv_epi8(a: Self, b: Self, mask: Self) -> Self {
-            arch::_mm256_blendv_epi8(a, b, mask)
+        unsafe fn andnot_si(a: Self, b: Self) -> Self {
+            arch::_mm256_andnot_si256(a, b)
         }
 
         unsafe fn cmpeq_epi8(a: Self, b: Self) -> Self {
@@ -135,9 +132,12 @@ mod avx2 {
             arch::_mm256_movemask_epi8(a) as u32
         }
 
-        #[expect(clippy::cast_possible_wrap)]
-        unsafe fn set1_epi8(a: u8) -> Self {
-            arch::_mm256_set1_epi8(a as i8)
+        unsafe fn setzero_si() -> Self {
+            arch::_mm256_setzero_si256()
+        }
+
+        unsafe fn xor_si(a: Self, b: Self) -> Self {
+            arch::_mm256_xor_si256(a, b)
         }
     }
 }
@@ -198,7 +198,7 @@ impl Method {
     }
 }
 
-const BUFFER_ALIGNMENT: usize = 32;
+pub(crate) const BUFFER_ALIGNMENT: usize = 32;
 const _: () = assert!(mem::align_of::<AlignedBytes<1>>() == BUFFER_ALIGNMENT);
 
 #[derive(Clone, Debug)]
@@ -233,6 +233,11 @@ impl MaskedByte {
     pub(crate) fn is_unmasked(self) -> bool {
         self == Self::UNMASKED
     }
+
+    #[must_use]
+    fn accepts(self, word: u8, other: u8) -> bool {
+        (word ^ other) & !self.0 == 0
+    }
 }
 
 impl From<u8> for MaskedByte {
@@ -247,6 +252,118 @@ impl From<MaskedByte> for u8 {
     }
 }
 
+/// A single byte of a pattern, where only the bits set in `mask` need to match `word`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct PatternByte {
+    pub(crate) word: u8,
+    pub(crate) mask: u8,
+}
+
+impl PatternByte {
+    pub(crate) const WILDCARD: Self = Self { word: 0, mask: 0 };
+
+    #[must_use]
+    pub(crate) fn exact(byte: u8) -> Self {
+        Self {
+            word: byte,
+            mask: 0xFF,
+        }
+    }
+
+    /// Each nibble is either `Some` exact match, or `None` for a wildcard.
+    #[must_use]
+    pub(crate) fn from_nibbles(high: Option<u8>, low: Option<u8>) -> Self {
+        let (high_word, high_mask) = high.map_or((0, 0), |x| (x << 4, 0xF0));
+        let (low_word, low_mask) = low.map_or((0, 0), |x| (x, 0x0F));
+        Self {
+            word: high_word | low_word,
+            mask: high_mask | low_mask,
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn accepts(self, byte: u8) -> bool {
+        (self.word ^ byte) & self.mask == 0
+    }
+}
+
+impl From<Option<u8>> for PatternByte {
+    fn from(value: Option<u8>) -> Self {
+        value.map_or(Self::WILDCARD, Self::exact)
+    }
+}
+
+/// A set of bytes, any of which may match a single position in the haystack.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RawByteSet {
+    pub bits: [u64; 4],
+}
+
+impl RawByteSet {
+    pub(crate) const EMPTY: Self = Self { bits: [0; 4] };
+
+    #[must_use]
+    pub(crate) fn contains(&self, byte: u8) -> bool {
+        self.bits[usize::from(byte >> 6)] & (1 << (byte & 0x3F)) != 0
+    }
+
+    pub(crate) fn insert(&mut self, byte: u8) {
+        self.bits[usize::from(byte >> 6)] |= 1 << (byte & 0x3F);
+    }
+
+    #[must_use]
+    pub(crate) fn union(self, other: Self) -> Self {
+        let mut bits = self.bits;
+        for (l, r) in bits.iter_mut().zip(other.bits) {
+            *l |= r;
+        }
+        Self { bits }
+    }
+
+    /// Every byte which is not in the set.
+    #[must_use]
+    pub(crate) fn complement(self) -> Self {
+        Self {
+            bits: self.bits.map(|x| !x),
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn len(&self) -> usize {
+        self.bits.iter().map(|x| x.count_ones() as usize).sum()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=u8::MAX).filter(|&x| self.contains(x))
+    }
+
+    /// The narrowest [`PatternByte`] which accepts every byte in the set, and whether it accepts nothing else.
+    #[must_use]
+    pub(crate) fn approximate(&self) -> (PatternByte, bool) {
+        let Some(first) = self.iter().next() else {
+            return (PatternByte::WILDCARD, false);
+        };
+        let mask = !self.iter().fold(0, |diff, x| diff | (x ^ first));
+        let approximation = PatternByte {
+            word: first & mask,
+            mask,
+        };
+        let exact = self.len() == 1 << mask.count_zeros();
+        (approximation, exact)
+    }
+}
+
+impl From<PatternByte> for RawByteSet {
+    fn from(value: PatternByte) -> Self {
+        let mut set = Self::EMPTY;
+        for byte in (0..=u8::MAX).filter(|&x| value.accepts(x)) {
+            set.insert(byte);
+        }
+        set
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct DynamicPattern {
     word: NonNull<u8>,
@@ -257,12 +374,7 @@ pub(crate) struct DynamicPattern {
 
 impl DynamicPattern {
     #[must_use]
-    pub(crate) fn len(&self) -> usize {
-        self.len
-    }
-
-    #[must_use]
-    pub(crate) fn from_bytes(bytes: &[Option<u8>]) -> Self {
+    pub(crate) fn from_bytes(bytes: &[PatternByte]) -> Self {
         const _: () = assert!(BUFFER_ALIGNMENT != 0);
         const _: () = assert!(BUFFER_ALIGNMENT % 2 == 0);
         let layout = Layout::from_size_align(bytes.len().max(1), BUFFER_ALIGNMENT)
@@ -277,18 +389,12 @@ impl DynamicPattern {
 
         let word_slice = unsafe { slice::from_raw_parts_mut(word.as_ptr(), layout.size()) };
         for (l, r) in word_slice.iter_mut().zip(bytes) {
-            *l = match r {
-                Some(byte) => *byte,
-                None => 0,
-            };
+            *l = r.word & r.mask;
         }
 
         let mask_slice = unsafe { slice::from_raw_parts_mut(mask.as_ptr(), layout.size()) };
         for (l, r) in mask_slice.iter_mut().zip(bytes) {
-            *l = match r {
-                Some(_) => MaskedByte::UNMASKED,
-                None => MaskedByte::MASKED,
-            };
+            *l = MaskedByte(!r.mask);
         }
 
         Self {
@@ -378,6 +484,24 @@ impl<'a> PatternRef<'a> {
         self.method
     }
 
+    /// SAFETY:
+    /// * `offset..offset + len` must lie within the buffers referenced by `self`
+    /// * `offset` must be a multiple of `BUFFER_ALIGNMENT`
+    #[must_use]
+    pub(crate) unsafe fn subpattern(&self, offset: usize, len: usize) -> PatternRef<'a> {
+        debug_assert_eq!(offset % BUFFER_ALIGNMENT, 0);
+        let method = Method::from_size(len);
+        let vectorizable_boundary = method.compute_vectorizable_boundary(len);
+        Self {
+            word: self.word.add(offset),
+            mask: self.mask.add(offset),
+            size: len,
+            method,
+            vectorizable_boundary,
+            _phantom: PhantomData,
+        }
+    }
+
     #[must_use]
     pub(crate) fn len(&self) -> usize {
         self.size
@@ -393,6 +517,17 @@ impl<'a> PatternRef<'a> {
         unsafe { slice::from_raw_parts(self.mask.as_ptr(), self.len()) }
     }
 
+    /// The bytes the pattern was built from.
+    pub(crate) fn bytes(&self) -> impl Iterator<Item = PatternByte> + '_ {
+        self.word_slice()
+            .iter()
+            .zip(self.mask_slice())
+            .map(|(&word, &mask)| PatternByte {
+                word,
+                mask: !u8::from(mask),
+            })
+    }
+
     #[must_use]
     unsafe fn cmpeq_scalar_range(&self, other: ThinSlice<u8>, range: RangeFrom<usize>) -> bool {
         let mut word = self.word.add(range.start);
@@ -402,7 +537,7 @@ impl<'a> PatternRef<'a> {
         while other.start != other.end {
             let word_val = word.read();
             let other_val = other.start.read();
-            if word_val != other_val && mask.read().is_unmasked() {
+            if !mask.read().accepts(word_val, other_val) {
                 return false;
             }
             word = word.add(1);
@@ -453,16 +588,16 @@ impl<'a> PatternRef<'a> {
         let mut word = self.word.cast::<T>();
         let mut mask = self.mask.cast::<T>();
         let (mut trimmed, extra) = other.split_at_unchecked::<T, u8>(self.vectorizable_boundary);
-        let all_ones = T::set1_epi8(0xFF);
+        let zero = T::setzero_si();
 
         while trimmed.start != trimmed.end {
             let word_vec = T::load(word);
             let mask_vec = T::load(mask);
             let trimmed_vec = T::loadu(trimmed.start);
 
-            let cmpeq = T::cmpeq_epi8(trimmed_vec, word_vec);
-            let blendv = T::blendv_epi8(cmpeq, all_ones, mask_vec);
-            let movemask = T::movemask_epi8(blendv);
+            let difference = T::andnot_si(mask_vec, T::xor_si(trimmed_vec, word_vec));
+            let cmpeq = T::cmpeq_epi8(difference, zero);
+            let movemask = T::movemask_epi8(cmpeq);
             if movemask != T::Integer::MAX {
                 return false;
             }
@@ -547,7 +682,9 @@ mod test {
     use super::{
         DynamicPattern,
         Method,
+        PatternByte,
         PatternRef,
+        RawByteSet,
     };
 
     macro_rules! make_pattern {
@@ -556,8 +693,8 @@ mod test {
                 .as_bytes()
                 .iter()
                 .map(|&x| match x {
-                    b'?' => None,
-                    _ => Some(x),
+                    b'?' => PatternByte::WILDCARD,
+                    _ => PatternByte::exact(x),
                 })
                 .collect::<Vec<_>>();
             let dynamic = DynamicPattern::from_bytes(&bytes);
@@ -744,4 +881,88 @@ mod test {
         assert!(!pattern.cmpeq(b"where the fear has gone there will be nothin"));
         assert!(!pattern.cmpeq(b""));
     }
+
+    #[test]
+    fn test_partial_masks() {
+        // one length for each method, so that every comparison path sees partial masks
+        for len in [3u8, 6, 11, 20, 45] {
+            let bytes: Vec<_> = (0..len)
+                .map(|i| PatternByte {
+                    word: i.wrapping_mul(0x3B),
+                    mask: [0xF8, 0xFF, 0x0F, 0x00, 0x81][usize::from(i) % 5],
+                })
+                .collect();
+            let dynamic = DynamicPattern::from_bytes(&bytes);
+            let pattern = PatternRef::from(&dynamic);
+
+            let word: Vec<_> = bytes.iter().map(|x| x.word).collect();
+            assert!(pattern.cmpeq(&word), "len = {len}");
+            for (i, byte) in bytes.iter().enumerate() {
+                for bit in 0..8 {
+                    let mut other = word.clone();
+                    other[i] ^= 1 << bit;
+                    let expected = byte.mask & (1 << bit) == 0;
+                    assert_eq!(
+                        pattern.cmpeq(&other),
+                        expected,
+                        "len = {len}, i = {i}, bit = {bit}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_byte_set() {
+        let set = |bytes: &[u8]| {
+            let mut set = RawByteSet::EMPTY;
+            for &byte in bytes {
+                set.insert(byte);
+            }
+            set
+        };
+
+        let (approximation, exact) = set(&[0xE8, 0xE9]).approximate();
+        assert_eq!(
+            approximation,
+            PatternByte {
+                word: 0xE8,
+                mask: 0xFE
+            }
+        );
+        assert!(exact);
+
+        let (approximation, exact) = set(&(0x00..=0x1F).collect::<Vec<_>>()).approximate();
+        assert_eq!(
+            approximation,
+            PatternByte {
+                word: 0x00,
+                mask: 0xE0
+            }
+        );
+        assert!(exact);
+
+        let (approximation, exact) = set(&[0x10, 0x11, 0x12]).approximate();
+        assert_eq!(
+            approximation,
+            PatternByte {
+                word: 0x10,
+                mask: 0xFC
+            }
+        );
+        assert!(!exact);
+
+        let (approximation, exact) = set(&[0x42]).approximate();
+        assert_eq!(approximation, PatternByte::exact(0x42));
+        assert!(exact);
+
+        let set = RawByteSet::from(PatternByte::from_nibbles(Some(0x4), None));
+        assert_eq!(set.len(), 16);
+        assert!(set.contains(0x40) && set.contains(0x4F));
+        assert!(!set.contains(0x50));
+        assert_eq!(
+            set.approximate(),
+            (PatternByte::from_nibbles(Some(0x4), None), true)
+        );
+    }
 }
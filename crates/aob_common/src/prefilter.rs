@@ -1,4 +1,9 @@
 use crate::pattern::PatternRef;
+use aho_corasick::{
+    AhoCorasick,
+    Input,
+    MatchKind,
+};
 use memchr::arch::all::packedpair::{
     Finder as GenericFinder,
     Pair as PackedPair,
@@ -8,6 +13,13 @@ use memchr::arch::x86_64::{
     avx2::packedpair::Finder as Avx2Finder,
     sse2::packedpair::Finder as Sse2Finder,
 };
+use std::{
+    cmp::Reverse,
+    collections::{
+        hash_map::Entry,
+        HashMap,
+    },
+};
 
 enum InnerError {
     NotFound,
@@ -84,7 +96,7 @@ impl From<&CompiledPrefilter> for RawPrefilter {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 enum Inner {
     Length {
         len: usize,
@@ -112,7 +124,7 @@ enum Inner {
     },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 pub(crate) struct CompiledPrefilter {
     inner: Inner,
 }
@@ -209,6 +221,32 @@ impl CompiledPrefilter {
         Self::from_prefix(needle[prefix_offset], prefix_offset)
     }
 
+    /// The bytes the prefilter searches for, or `None` if it only checks the length of the haystack.
+    #[must_use]
+    pub(crate) fn key(&self) -> Option<Key> {
+        match RawPrefilter::from(self) {
+            RawPrefilter::Length { .. } => None,
+            RawPrefilter::Prefix {
+                prefix,
+                prefix_offset,
+            } => Some(Key {
+                prefix,
+                prefix_offset,
+                postfix: None,
+            }),
+            RawPrefilter::PrefixPostfix {
+                prefix,
+                prefix_offset,
+                postfix,
+                postfix_offset,
+            } => Some(Key {
+                prefix,
+                prefix_offset: prefix_offset.into(),
+                postfix: Some((postfix, postfix_offset.into())),
+            }),
+        }
+    }
+
     #[must_use]
     pub(crate) fn find_iter<'haystack, 'prefilter>(
         &'prefilter self,
@@ -221,6 +259,31 @@ impl CompiledPrefilter {
         }
     }
 
+    /// Finds the last position before `end` which the prefilter would yield, searching backwards through `haystack`.
+    ///
+    /// The packed pair finders only search forwards, so the prefix is searched for on its own before the postfix is compared.
+    #[must_use]
+    pub(crate) fn rfind(&self, haystack: &[u8], mut end: usize) -> Option<usize> {
+        if let Inner::Length { len } = self.inner {
+            let last = haystack.len().checked_sub(len)?;
+            return end.checked_sub(1).map(|end| end.min(last));
+        }
+
+        let key = self.key()?;
+        loop {
+            let limit = haystack.len().min(end.saturating_add(key.prefix_offset));
+            let position = memchr::memrchr(key.prefix, &haystack[..limit])?;
+            let start = position.checked_sub(key.prefix_offset)?;
+            let matches = key.postfix.is_none_or(|(postfix, postfix_offset)| {
+                haystack.get(start + postfix_offset) == Some(&postfix)
+            });
+            if matches {
+                break Some(start);
+            }
+            end = start;
+        }
+    }
+
     fn find(&self, haystack: &[u8]) -> Result<usize, InnerError> {
         match self.inner {
             Inner::Length { len } => {
@@ -320,10 +383,161 @@ impl<'haystack, 'prefilter> Iterator for Iter<'haystack, 'prefilter> {
     }
 }
 
+/// The exact bytes a prefilter searches for, relative to the positions it yields.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Key {
+    pub(crate) prefix: u8,
+    pub(crate) prefix_offset: usize,
+    /// A second byte, and its offset, which must also match.
+    pub(crate) postfix: Option<(u8, usize)>,
+}
+
+/// A run of exact bytes which a needle within a [`MultiPrefilter`] is searched for by.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Literal {
+    pub(crate) bytes: Vec<u8>,
+    /// The offset of the bytes from the position the needle's own prefilter would yield.
+    pub(crate) offset: usize,
+}
+
+impl Literal {
+    /// The longest run of exact bytes in `pattern`, preferring earlier runs, or `None` if there aren't any.
+    #[must_use]
+    pub(crate) fn from_pattern(pattern: PatternRef<'_>) -> Option<Self> {
+        let mask = pattern.mask_slice();
+        let (offset, len) = mask
+            .split(|x| !x.is_unmasked())
+            .scan(0, |offset, run| {
+                let start = *offset;
+                *offset += run.len() + 1;
+                Some((start, run.len()))
+            })
+            .min_by_key(|&(_, len)| Reverse(len))
+            .filter(|&(_, len)| len > 0)?;
+        Some(Self {
+            bytes: pattern.word_slice()[offset..][..len].to_vec(),
+            offset,
+        })
+    }
+}
+
+/// A prefilter which searches for the [`Literal`]s of many needles in a single pass over the haystack.
+///
+/// An Aho-Corasick automaton finds the leftmost literal, using vectorized searches where the cpu supports them,
+/// then every literal beginning with the same byte is compared at that position.
+#[derive(Clone, Debug)]
+pub(crate) struct MultiPrefilter {
+    automaton: AhoCorasick,
+    /// Each distinct literal, identified by its position.
+    literals: Vec<Vec<u8>>,
+    /// The literals beginning with each byte.
+    buckets: Vec<Vec<usize>>,
+    /// The needles searched for by each literal, along with the literal's offset within each of them.
+    needles: Vec<Vec<(usize, usize)>>,
+}
+
+impl MultiPrefilter {
+    /// Builds a prefilter from the literal of each needle, identified by its index.
+    #[must_use]
+    pub(crate) fn new(literals: impl IntoIterator<Item = (usize, Literal)>) -> Self {
+        let mut ids: HashMap<_, usize> = HashMap::new();
+        let mut distinct = Vec::new();
+        let mut buckets = vec![Vec::new(); 256];
+        let mut needles: Vec<Vec<_>> = Vec::new();
+        for (index, literal) in literals {
+            match ids.entry(literal.bytes) {
+                Entry::Occupied(id) => needles[*id.get()].push((index, literal.offset)),
+                Entry::Vacant(id) => {
+                    buckets[usize::from(id.key()[0])].push(distinct.len());
+                    distinct.push(id.key().clone());
+                    needles.push(vec![(index, literal.offset)]);
+                    id.insert(distinct.len() - 1);
+                }
+            }
+        }
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build(&distinct)
+            .expect("the literals of a set should fit in an automaton");
+        Self {
+            automaton,
+            literals: distinct,
+            buckets,
+            needles,
+        }
+    }
+
+    /// Yields the index of each needle whose literal is found, along with the position that needle's own prefilter would have yielded.
+    ///
+    /// Positions are yielded in the order their literals appear in the haystack, and then in the order the needles were given.
+    #[must_use]
+    pub(crate) fn find_iter<'haystack, 'prefilter>(
+        &'prefilter self,
+        haystack: &'haystack [u8],
+    ) -> MultiIter<'haystack, 'prefilter> {
+        MultiIter {
+            haystack,
+            prefilter: self,
+            position: None,
+            checked: (0, 0),
+            last_offset: 0,
+        }
+    }
+}
+
+pub(crate) struct MultiIter<'haystack, 'prefilter> {
+    haystack: &'haystack [u8],
+    prefilter: &'prefilter MultiPrefilter,
+    /// The position of the last literal found.
+    position: Option<usize>,
+    /// How many of the literals in the bucket at `position` have been compared,
+    /// and how many needles of the next one have been yielded.
+    checked: (usize, usize),
+    last_offset: usize,
+}
+
+impl Iterator for MultiIter<'_, '_> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(position) = self.position {
+                let bucket = &self.prefilter.buckets[usize::from(self.haystack[position])];
+                while let Some(&id) = bucket.get(self.checked.0) {
+                    if self.haystack[position..].starts_with(&self.prefilter.literals[id]) {
+                        if let Some(&(index, offset)) =
+                            self.prefilter.needles[id].get(self.checked.1)
+                        {
+                            self.checked.1 += 1;
+                            if let Some(anchor) = position.checked_sub(offset) {
+                                return Some((index, anchor));
+                            }
+                            continue;
+                        }
+                    }
+                    self.checked = (self.checked.0 + 1, 0);
+                }
+            }
+
+            let input = Input::new(self.haystack).span(self.last_offset..self.haystack.len());
+            let position = self.prefilter.automaton.find(input)?.start();
+            self.position = Some(position);
+            self.checked = (0, 0);
+            self.last_offset = position + 1;
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::RawPrefilter;
-    use crate::DynamicNeedle;
+    use super::{
+        Literal,
+        RawPrefilter,
+    };
+    use crate::{
+        DynamicNeedle,
+        Needle as _,
+    };
 
     #[test]
     fn test_prefilter() {
@@ -398,6 +612,37 @@ mod test {
             }
         );
 
+        let pre = DynamicNeedle::from_ida("1? 22 ?3 44 5?").unwrap();
+        assert_eq!(
+            pre.serialize_prefilter(),
+            RawPrefilter::PrefixPostfix {
+                prefix: 0x22,
+                prefix_offset: 1,
+                postfix: 0x44,
+                postfix_offset: 3
+            }
+        );
+
+        let pre = DynamicNeedle::from_masked(&[0x11, 0x22, 0x33], &[0xFF, 0xF8, 0xFF]);
+        assert_eq!(
+            pre.serialize_prefilter(),
+            RawPrefilter::PrefixPostfix {
+                prefix: 0x11,
+                prefix_offset: 0,
+                postfix: 0x33,
+                postfix_offset: 2
+            }
+        );
+
+        let pre = DynamicNeedle::from_masked(&[0x11, 0x22, 0x33], &[0x0F, 0xFF, 0xFE]);
+        assert_eq!(
+            pre.serialize_prefilter(),
+            RawPrefilter::Prefix {
+                prefix: 0x22,
+                prefix_offset: 1
+            }
+        );
+
         let pre = make_prefilter![None, Some(0x70)];
         assert_eq!(
             pre,
@@ -407,4 +652,77 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn test_variable_prefilter() {
+        let pre = DynamicNeedle::from_ida("11 [2-4] 22 ? 33").unwrap();
+        assert_eq!(
+            pre.serialize_prefilter(),
+            RawPrefilter::PrefixPostfix {
+                prefix: 0x22,
+                prefix_offset: 0,
+                postfix: 0x33,
+                postfix_offset: 2
+            }
+        );
+
+        let pre = DynamicNeedle::from_ida("? [0-1] ?").unwrap();
+        assert_eq!(pre.serialize_prefilter(), RawPrefilter::Length { len: 2 });
+    }
+
+    #[test]
+    fn test_set_prefilter() {
+        let pre = DynamicNeedle::from_ida("(E8|E9) 11 ? 22 (00-7F)").unwrap();
+        assert_eq!(
+            pre.serialize_prefilter(),
+            RawPrefilter::PrefixPostfix {
+                prefix: 0x11,
+                prefix_offset: 1,
+                postfix: 0x22,
+                postfix_offset: 3
+            }
+        );
+
+        let pre = DynamicNeedle::from_ida("(10|20|30) 11").unwrap();
+        assert_eq!(
+            pre.serialize_prefilter(),
+            RawPrefilter::Prefix {
+                prefix: 0x11,
+                prefix_offset: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_literal() {
+        let literal = |pattern| {
+            DynamicNeedle::from_ida(pattern)
+                .unwrap()
+                .find_iter(&[])
+                .literal()
+        };
+
+        // the longest run wins, then the earliest
+        let expected = Literal {
+            bytes: vec![0x22, 0x33],
+            offset: 2,
+        };
+        assert_eq!(literal("11 ? 22 33 ? 44"), Some((expected.clone(), 0)));
+        assert_eq!(literal("? 1? 22 33 ? 44 55"), Some((expected, 0)));
+
+        // variable length needles are searched for by their anchor, so the literal is relative to it
+        assert_eq!(
+            literal("11 [2-4] ? 22 33 44 ? 55"),
+            Some((
+                Literal {
+                    bytes: vec![0x22, 0x33, 0x44],
+                    offset: 1,
+                },
+                5
+            ))
+        );
+
+        assert_eq!(literal("? ?"), None);
+        assert_eq!(literal("? [-] 22 33"), None);
+    }
 }
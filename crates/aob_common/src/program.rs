@@ -0,0 +1,1139 @@
+use crate::{
+    ast::{
+        self,
+        Node,
+    },
+    field::RawField,
+    pattern::{
+        PatternByte,
+        PatternRef,
+        RawByteSet,
+        BUFFER_ALIGNMENT,
+    },
+};
+use std::{
+    collections::{
+        hash_map::Entry,
+        HashMap,
+        HashSet,
+    },
+    mem,
+    ops::Range,
+};
+
+/// A single step in matching a variable length needle against a haystack.
+///
+/// Execution begins at the first instruction, and a match is found once execution
+/// runs past the last instruction.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RawInstruction {
+    /// Compare the next `len` bytes of the haystack against the needle's buffers, starting at `offset`.
+    Chunk { offset: usize, len: usize },
+    /// Skip anywhere from `min` to `max` bytes of the haystack, where a `max` of `None` is unbounded.
+    Skip { min: usize, max: Option<usize> },
+    /// Continue execution at `first`, falling back to `second` if that fails.
+    Split { first: usize, second: usize },
+    /// Continue execution at `target`.
+    Goto { target: usize },
+    /// Check that the byte `back` positions behind the current position of the haystack is in `set`.
+    Member { back: usize, set: RawByteSet },
+    /// Record the position `back` positions behind the current position of the haystack into `slot`.
+    Save { slot: usize, back: usize },
+    /// Check that the byte `back` positions behind the current position of the haystack equals the byte at the position recorded in `slot`,
+    /// unless nothing has been recorded into it yet.
+    Backreference { slot: usize, back: usize },
+    /// Check that the number beginning `back` positions behind the current position of the haystack is within the range of `field`.
+    Field { back: usize, field: RawField },
+}
+
+/// The chunk of a variable length needle that the prefilter searches for.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RawAnchor {
+    /// Where the chunk lies within the needle's buffers.
+    pub offset: usize,
+    /// The length of the chunk.
+    pub len: usize,
+    /// The fewest number of bytes that can precede the chunk in a match.
+    pub min_distance: usize,
+    /// The greatest number of bytes that can precede the chunk in a match, where `None` is unbounded.
+    pub max_distance: Option<usize>,
+}
+
+/// The instructions of a variable length needle, along with the chunk its prefilter searches for.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct RawProgram<'a> {
+    pub instructions: &'a [RawInstruction],
+    pub anchor: Option<RawAnchor>,
+}
+
+/// The names of a needle's captures, along with their offsets within a match of a fixed length needle.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct RawCaptures<'a> {
+    /// The name of each capture, in the order their opening parentheses appear.
+    pub names: &'a [Option<&'a str>],
+    /// The offset of the cursor from the start of a match, followed by the offsets of the start and end of each capture,
+    /// or empty if the needle has a variable length.
+    pub offsets: &'a [usize],
+    /// Pairs of offsets from the start of a match whose bytes must be equal, or empty if the needle has a variable length.
+    pub backreferences: &'a [(usize, usize)],
+    /// The numbers which must be within a range, along with their offsets from the start of a match, or empty if the needle has a variable length.
+    pub fields: &'a [(usize, RawField)],
+}
+
+/// The slot which the cursor is recorded into, followed by the slots of each capture.
+pub(crate) const CURSOR_SLOT: usize = 0;
+
+/// The position held by a slot which nothing has been recorded into yet.
+pub(crate) const UNSET: usize = usize::MAX;
+
+/// The longest fixed length jump which is compared as wildcards, rather than skipped over.
+const MAX_INLINE_JUMP: usize = 256;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Program {
+    instructions: Vec<RawInstruction>,
+    anchor: Option<RawAnchor>,
+}
+
+impl Program {
+    #[must_use]
+    pub(crate) fn as_raw(&self) -> RawProgram<'_> {
+        RawProgram {
+            instructions: &self.instructions,
+            anchor: self.anchor,
+        }
+    }
+}
+
+/// The result of compiling a sequence of [`Node`]s.
+pub(crate) struct Compiled {
+    /// The contents of the needle's buffers.
+    pub(crate) bytes: Vec<PatternByte>,
+    /// The program needed to match a variable length needle, or `None` if the needle has a fixed length.
+    pub(crate) program: Option<Program>,
+    /// The fewest number of bytes the needle can match.
+    pub(crate) len: usize,
+    /// The name of each capture, in the order their opening parentheses appear.
+    pub(crate) capture_names: Vec<Option<String>>,
+    /// The offset of the cursor from the start of a match, followed by the offsets of the start and end of each capture,
+    /// or empty if the needle has a variable length.
+    pub(crate) capture_offsets: Vec<usize>,
+    /// Pairs of offsets from the start of a match whose bytes must be equal, or empty if the needle has a variable length.
+    pub(crate) backreferences: Vec<(usize, usize)>,
+    /// The numbers which must be within a range, along with their offsets from the start of a match, or empty if the needle has a variable length.
+    pub(crate) fields: Vec<(usize, RawField)>,
+}
+
+impl Compiled {
+    #[must_use]
+    pub(crate) fn from_nodes(nodes: &[Node]) -> Self {
+        let len = ast::sequence_min_len(nodes)
+            .expect("the length of a parsed pattern should fit in a usize");
+        let mut compiler = Compiler {
+            distance: (0, Some(0)),
+            ..Compiler::default()
+        };
+        outside_names(nodes, &mut compiler.outside);
+        compiler.emit(nodes);
+        if nodes.iter().all(Node::is_fixed_len)
+            && compiler.members.is_empty()
+            && compiler.instructions.is_empty()
+        {
+            let mut capture_offsets = vec![0; slots_len(compiler.captures.len())];
+            for (position, slot) in compiler.saves {
+                capture_offsets[slot] = position;
+            }
+            let backreferences = compiler
+                .backreferences
+                .iter()
+                .map(|&(position, slot)| (capture_offsets[slot], position))
+                .collect();
+            Self {
+                bytes: compiler.pending,
+                program: None,
+                len,
+                capture_names: compiler.captures,
+                capture_offsets,
+                backreferences,
+                fields: compiler.fields,
+            }
+        } else {
+            compiler.flush();
+            let anchor = compiler.select_anchor();
+            Self {
+                bytes: compiler.bytes,
+                program: Some(Program {
+                    instructions: compiler.instructions,
+                    anchor,
+                }),
+                len,
+                capture_names: compiler.captures,
+                capture_offsets: Vec::new(),
+                backreferences: Vec::new(),
+                fields: Vec::new(),
+            }
+        }
+    }
+}
+
+/// The number of slots needed to record the cursor and `captures` captures.
+#[must_use]
+pub(crate) const fn slots_len(captures: usize) -> usize {
+    CURSOR_SLOT + 1 + captures * 2
+}
+
+/// Adds the name of every wildcard within `nodes` which lies outside of any alternation to `names`.
+fn outside_names(nodes: &[Node], names: &mut HashSet<String>) {
+    for node in nodes {
+        match node {
+            Node::NamedWildcard(name) => {
+                names.insert(name.clone());
+            }
+            Node::Capture { nodes, .. } => outside_names(nodes, names),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Default)]
+struct Compiler {
+    bytes: Vec<PatternByte>,
+    instructions: Vec<RawInstruction>,
+    pending: Vec<PatternByte>,
+    /// The sets which can't be expressed as a [`PatternByte`], along with their position within the pending bytes.
+    members: Vec<(usize, RawByteSet)>,
+    /// The numbers which must be within a range, along with their position within the pending bytes.
+    fields: Vec<(usize, RawField)>,
+    /// The name of each capture seen so far.
+    captures: Vec<Option<String>>,
+    /// The slots to record the bounds of captures into, along with their position within the pending bytes.
+    saves: Vec<(usize, usize)>,
+    /// The slots of the capture made by the wildcards of each name, along with whether the first of them outside of any
+    /// alternation has been reached.
+    named_wildcards: HashMap<String, (usize, bool)>,
+    /// The names of wildcards which lie outside of any alternation.
+    outside: HashSet<String>,
+    /// The wildcards which must equal the byte recorded in a slot, along with their position within the pending bytes.
+    backreferences: Vec<(usize, usize)>,
+    /// How deeply nested within alternations the compiler currently is.
+    depth: usize,
+    /// The range of distances from the start of a match to the first pending byte, only tracked outside of alternations.
+    distance: (usize, Option<usize>),
+    /// Every chunk that must appear in a match, in order.
+    anchors: Vec<RawAnchor>,
+}
+
+impl Compiler {
+    fn emit(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            match node {
+                Node::Byte(byte) => self.pending.push(*byte),
+                Node::Set(set) => {
+                    let (approximation, exact) = set.approximate();
+                    if !exact {
+                        self.members.push((self.pending.len(), *set));
+                    }
+                    self.pending.push(approximation);
+                }
+                Node::Field(field) => {
+                    self.fields.push((self.pending.len(), *field));
+                    self.pending.extend(field.approximate());
+                }
+                Node::Jump { min, max } if Some(*min) == *max && *min <= MAX_INLINE_JUMP => self
+                    .pending
+                    .extend(std::iter::repeat_n(PatternByte::WILDCARD, *min)),
+                Node::Jump { min, max } => {
+                    self.flush();
+                    self.advance(*min, *max);
+                    self.instructions.push(RawInstruction::Skip {
+                        min: *min,
+                        max: *max,
+                    });
+                }
+                Node::Alternation(alternatives) => {
+                    self.flush();
+                    let min = node
+                        .min_len()
+                        .expect("the length of a parsed pattern should fit in a usize");
+                    self.advance(min, node.max_len());
+                    self.depth += 1;
+                    let mut gotos = Vec::new();
+                    for (i, alternative) in alternatives.iter().enumerate() {
+                        let split = (i + 1 < alternatives.len()).then(|| {
+                            self.instructions.push(RawInstruction::Split {
+                                first: self.instructions.len() + 1,
+                                second: usize::MAX,
+                            });
+                            self.instructions.len() - 1
+                        });
+                        self.emit(alternative);
+                        self.flush();
+                        if let Some(split) = split {
+                            gotos.push(self.instructions.len());
+                            self.instructions
+                                .push(RawInstruction::Goto { target: usize::MAX });
+                            let next = self.instructions.len();
+                            if let RawInstruction::Split { second, .. } =
+                                &mut self.instructions[split]
+                            {
+                                *second = next;
+                            }
+                        }
+                    }
+
+                    let next = self.instructions.len();
+                    for goto in gotos {
+                        self.instructions[goto] = RawInstruction::Goto { target: next };
+                    }
+                    self.depth -= 1;
+                }
+                Node::Capture { name, nodes } => {
+                    let slot = slots_len(self.captures.len());
+                    self.captures.push(name.clone());
+                    self.saves.push((self.pending.len(), slot));
+                    self.emit(nodes);
+                    self.saves.push((self.pending.len(), slot + 1));
+                }
+                Node::Cursor => self.saves.push((self.pending.len(), CURSOR_SLOT)),
+                Node::NamedWildcard(name) => {
+                    let position = self.pending.len();
+                    self.pending.push(PatternByte::WILDCARD);
+                    let (slot, repeat) = match self.named_wildcards.entry(name.clone()) {
+                        Entry::Occupied(entry) => {
+                            let (slot, reached) = entry.into_mut();
+                            let repeat = *reached;
+                            // an alternative might not be taken, so until the first wildcard outside of any alternation
+                            // is reached, each one records its own position after checking it equals the last one recorded
+                            if !repeat && self.outside.contains(name) {
+                                self.backreferences.push((position, *slot));
+                            }
+                            *reached |= self.depth == 0;
+                            (*slot, repeat)
+                        }
+                        Entry::Vacant(entry) => {
+                            let slot = slots_len(self.captures.len());
+                            self.captures.push(Some(name.clone()));
+                            entry.insert((slot, self.depth == 0));
+                            (slot, false)
+                        }
+                    };
+                    if repeat {
+                        self.backreferences.push((position, slot));
+                    } else {
+                        self.saves.push((position, slot));
+                        self.saves.push((position + 1, slot + 1));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves any pending bytes into their own chunk.
+    fn flush(&mut self) {
+        let len = self.pending.len();
+        if len > 0 {
+            let offset = self.bytes.len().next_multiple_of(BUFFER_ALIGNMENT);
+            self.bytes.resize(offset, PatternByte::WILDCARD);
+            self.bytes.append(&mut self.pending);
+            self.instructions
+                .push(RawInstruction::Chunk { offset, len });
+            for (position, set) in self.members.drain(..) {
+                self.instructions.push(RawInstruction::Member {
+                    back: len - position,
+                    set,
+                });
+            }
+            for (position, field) in self.fields.drain(..) {
+                self.instructions.push(RawInstruction::Field {
+                    back: len - position,
+                    field,
+                });
+            }
+            if self.depth == 0 {
+                let (min_distance, max_distance) = self.distance;
+                self.anchors.push(RawAnchor {
+                    offset,
+                    len,
+                    min_distance,
+                    max_distance,
+                });
+                self.advance(len, Some(len));
+            }
+        }
+        // a wildcard is compared against what was recorded before it, before it records its own position
+        let mut backreferences = self.backreferences.drain(..).peekable();
+        for (position, slot) in self.saves.drain(..) {
+            while let Some((back, slot)) = backreferences.next_if(|&(back, _)| back <= position) {
+                self.instructions.push(RawInstruction::Backreference {
+                    slot,
+                    back: len - back,
+                });
+            }
+            self.instructions.push(RawInstruction::Save {
+                slot,
+                back: len - position,
+            });
+        }
+        for (position, slot) in backreferences {
+            self.instructions.push(RawInstruction::Backreference {
+                slot,
+                back: len - position,
+            });
+        }
+    }
+
+    /// Moves the distance to the first pending byte past a node matching anywhere from `min` to `max` bytes.
+    fn advance(&mut self, min: usize, max: Option<usize>) {
+        if self.depth == 0 {
+            let (distance_min, distance_max) = self.distance;
+            self.distance = (
+                distance_min
+                    .checked_add(min)
+                    .expect("the length of a parsed pattern should fit in a usize"),
+                distance_max.zip(max).and_then(|(x, y)| x.checked_add(y)),
+            );
+        }
+    }
+
+    /// Picks the most selective chunk for the prefilter to search for.
+    ///
+    /// Chunks a bounded distance from the start of a match are preferred, then chunks with the most exact bytes, then earlier chunks.
+    #[must_use]
+    fn select_anchor(&self) -> Option<RawAnchor> {
+        self.anchors
+            .iter()
+            .map(|&anchor| {
+                let exact = self.bytes[anchor.offset..][..anchor.len]
+                    .iter()
+                    .filter(|x| x.mask == u8::MAX)
+                    .count();
+                (anchor, exact)
+            })
+            .filter(|&(_, exact)| exact > 0)
+            .rev()
+            .max_by_key(|&(anchor, exact)| (anchor.max_distance.is_some(), exact))
+            .map(|(anchor, _)| anchor)
+    }
+}
+
+/// A part of a sequence being decompiled, before its captures have been nested.
+enum Item {
+    Node(Node),
+    /// The position recorded into a slot, which begins a capture if it's the first slot of one, or else ends it.
+    Save(usize),
+}
+
+/// The bytes of a chunk being decompiled, along with everything recorded at positions within it.
+#[derive(Default)]
+struct Block {
+    nodes: Vec<Node>,
+    /// The numbers which must be within a range, along with their position within the chunk.
+    fields: Vec<(usize, RawField)>,
+    /// The slots recorded within the chunk, along with their position within it, in the order they're recorded.
+    saves: Vec<(usize, usize)>,
+    /// The wildcards which must equal the byte recorded in a slot, along with their position within the chunk.
+    backreferences: Vec<(usize, usize)>,
+}
+
+/// Rebuilds nodes equivalent to those a needle was compiled from, see [`Compiled::from_nodes`].
+struct Decompiler<'a> {
+    /// The name of each capture, in the order their opening parentheses appear.
+    names: &'a [Option<&'a str>],
+    /// The first slot of each capture which is made by named wildcards, rather than a capture of a wildcard.
+    named_wildcards: HashSet<usize>,
+}
+
+impl<'a> Decompiler<'a> {
+    #[must_use]
+    fn new(names: &'a [Option<&'a str>], named_wildcards: HashSet<usize>) -> Self {
+        Self {
+            names,
+            named_wildcards,
+        }
+    }
+
+    /// Decompiles the instructions of `program` within `range`, which must be a whole sequence.
+    #[must_use]
+    fn sequence(&mut self, program: ProgramRef<'_>, range: Range<usize>) -> Vec<Node> {
+        let mut items = Vec::new();
+        let mut block = Block::default();
+        let mut pc = range.start;
+        while pc < range.end {
+            let len = block.nodes.len();
+            match program.instructions[pc] {
+                RawInstruction::Chunk { offset, len } => {
+                    self.flush(&mut items, mem::take(&mut block));
+                    // SAFETY: chunks are guaranteed to lie within the buffer
+                    let chunk = unsafe { program.buffer.subpattern(offset, len) };
+                    block.nodes = chunk.bytes().map(Node::Byte).collect();
+                }
+                RawInstruction::Member { back, set } => block.nodes[len - back] = Node::Set(set),
+                RawInstruction::Field { back, field } => block.fields.push((len - back, field)),
+                RawInstruction::Save { slot, back } => block.saves.push((len - back, slot)),
+                RawInstruction::Backreference { slot, back } => {
+                    block.backreferences.push((len - back, slot));
+                }
+                RawInstruction::Skip { min, max } => {
+                    self.flush(&mut items, mem::take(&mut block));
+                    items.push(Item::Node(Node::Jump { min, max }));
+                }
+                RawInstruction::Split { .. } => {
+                    self.flush(&mut items, mem::take(&mut block));
+                    let (alternation, next) = self.alternation(program, pc);
+                    items.push(Item::Node(alternation));
+                    pc = next;
+                    continue;
+                }
+                RawInstruction::Goto { .. } => {
+                    unreachable!("a goto should only end an alternative")
+                }
+            }
+            pc += 1;
+        }
+        self.flush(&mut items, block);
+        self.nest(items)
+    }
+
+    /// Decompiles the alternation which begins with the split at `pc`, along with the instruction which follows it.
+    #[must_use]
+    fn alternation(&mut self, program: ProgramRef<'_>, mut pc: usize) -> (Node, usize) {
+        let mut alternatives = Vec::new();
+        let mut end = None;
+        // every alternative but the last begins with a split to the next, and ends with a goto past the last
+        while let Some(&RawInstruction::Split { first, second }) = program.instructions.get(pc) {
+            let RawInstruction::Goto { target } = program.instructions[second - 1] else {
+                break;
+            };
+            if end.is_some_and(|end| end != target) {
+                break;
+            }
+            end = Some(target);
+            alternatives.push(self.sequence(program, first..second - 1));
+            pc = second;
+        }
+        let end = end.expect("an alternation should begin with a split");
+        alternatives.push(self.sequence(program, pc..end));
+        // alternatives of a single byte each are parsed back as a set, so they're rebuilt as one
+        let set =
+            alternatives.iter().try_fold(
+                RawByteSet::EMPTY,
+                |set, alternative| match &alternative[..] {
+                    [Node::Byte(byte)] => Some(set.union(RawByteSet::from(*byte))),
+                    [Node::Set(other)] => Some(set.union(*other)),
+                    _ => None,
+                },
+            );
+        let node = set.map_or(Node::Alternation(alternatives), Node::Set);
+        (node, end)
+    }
+
+    /// Moves the nodes and recorded slots of `block` into `items`, in order.
+    fn flush(&self, items: &mut Vec<Item>, block: Block) {
+        let Block {
+            mut nodes,
+            fields,
+            saves,
+            backreferences,
+        } = block;
+        // a wildcard which doesn't record its own position repeats the capture it refers back to
+        for (position, slot) in backreferences {
+            if !saves.contains(&(position, slot)) {
+                let name = self
+                    .name(slot)
+                    .expect("a named wildcard should have a name");
+                nodes[position] = Node::NamedWildcard(name.to_owned());
+            }
+        }
+
+        let mut saves = saves.into_iter().peekable();
+        let mut nodes = nodes.into_iter().enumerate().peekable();
+        loop {
+            let position = nodes.peek().map_or(usize::MAX, |&(position, _)| position);
+            while let Some((_, slot)) = saves.next_if(|&(saved, _)| saved <= position) {
+                items.push(match slot {
+                    CURSOR_SLOT => Item::Node(Node::Cursor),
+                    slot => Item::Save(slot),
+                });
+            }
+            let Some((position, node)) = nodes.next() else {
+                break;
+            };
+            match fields.iter().find(|&&(offset, _)| offset == position) {
+                Some(&(_, field)) => {
+                    items.push(Item::Node(Node::Field(field)));
+                    for _ in 1..field.len {
+                        nodes.next();
+                    }
+                }
+                None => items.push(Item::Node(node)),
+            }
+        }
+    }
+
+    /// Nests `items` within the captures they lie between.
+    #[must_use]
+    fn nest(&self, items: Vec<Item>) -> Vec<Node> {
+        // each capture still open, along with the nodes which precede it
+        let mut open: Vec<(usize, Vec<Node>)> = Vec::new();
+        let mut nodes = Vec::new();
+        for item in items {
+            match item {
+                Item::Node(node) => nodes.push(node),
+                Item::Save(slot) if slot % 2 == 1 => open.push((slot, mem::take(&mut nodes))),
+                Item::Save(slot) => {
+                    let (first, outer) = open.pop().expect("a capture should end after it begins");
+                    debug_assert_eq!(first + 1, slot);
+                    let inner = mem::replace(&mut nodes, outer);
+                    nodes.push(self.capture(first, inner));
+                }
+            }
+        }
+        debug_assert!(open.is_empty());
+        nodes
+    }
+
+    /// The name of the capture whose first slot is `first`.
+    #[must_use]
+    fn name(&self, first: usize) -> Option<&'a str> {
+        self.names[(first - CURSOR_SLOT - 1) / 2]
+    }
+
+    /// The capture whose first slot is `first`, which matches `nodes`.
+    #[must_use]
+    fn capture(&self, first: usize, nodes: Vec<Node>) -> Node {
+        let name = self.name(first);
+        match (name, &nodes[..]) {
+            (Some(name), [Node::Byte(PatternByte::WILDCARD)])
+                if self.named_wildcards.contains(&first) =>
+            {
+                Node::NamedWildcard(name.to_owned())
+            }
+            _ => Node::Capture {
+                name: name.map(str::to_owned),
+                nodes,
+            },
+        }
+    }
+}
+
+/// Rebuilds nodes equivalent to those a fixed length needle was compiled from, see [`Compiled::from_nodes`].
+#[must_use]
+pub(crate) fn decompile_fixed(
+    bytes: impl IntoIterator<Item = PatternByte>,
+    names: &[Option<&str>],
+    capture_offsets: &[usize],
+    backreferences: &[(usize, usize)],
+    fields: &[(usize, RawField)],
+) -> Vec<Node> {
+    // captures are ordered by their opening parentheses, so each one is either within or after those before it
+    let mut saves = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    let bounds = |index| {
+        let slot = slots_len(index);
+        (slot, capture_offsets[slot], capture_offsets[slot + 1])
+    };
+    let mut order: Vec<_> = (0..names.len()).collect();
+    order.sort_by_key(|&index| (bounds(index).1, index));
+    for index in order {
+        let (slot, start, end) = bounds(index);
+        while let Some(&outer) = open.last() {
+            let (outer_slot, _, outer_end) = bounds(outer);
+            if end <= outer_end {
+                break;
+            }
+            saves.push((outer_end, outer_slot + 1));
+            open.pop();
+        }
+        saves.push((start, slot));
+        open.push(index);
+    }
+    while let Some(outer) = open.pop() {
+        let (outer_slot, _, outer_end) = bounds(outer);
+        saves.push((outer_end, outer_slot + 1));
+    }
+    // a capture can only be empty if the cursor is within it, but otherwise a cursor at the start of a match is the same as none at all
+    let cursor = capture_offsets
+        .get(CURSOR_SLOT)
+        .copied()
+        .unwrap_or_default();
+    let empty = saves.iter().rposition(|&(position, slot)| {
+        position == cursor && slot % 2 == 1 && capture_offsets[slot + 1] == cursor
+    });
+    if let Some(index) = empty {
+        saves.insert(index + 1, (cursor, CURSOR_SLOT));
+    } else if cursor != 0 {
+        let index = saves.partition_point(|&(position, slot)| {
+            position < cursor || (position == cursor && slot % 2 == 0)
+        });
+        saves.insert(index, (cursor, CURSOR_SLOT));
+    }
+
+    // the wildcard at each position is the innermost capture of that byte
+    let wildcard = |position| {
+        (0..names.len())
+            .rev()
+            .map(bounds)
+            .find(|&(_, start, end)| start == position && end == position + 1)
+            .map_or(usize::MAX, |(slot, ..)| slot)
+    };
+    let backreferences: Vec<_> = backreferences
+        .iter()
+        .map(|&(first, second)| (second, wildcard(first)))
+        .collect();
+    let named_wildcards = backreferences.iter().map(|&(_, slot)| slot).collect();
+    let block = Block {
+        nodes: bytes.into_iter().map(Node::Byte).collect(),
+        fields: fields.to_vec(),
+        saves,
+        backreferences,
+    };
+    let decompiler = Decompiler::new(names, named_wildcards);
+    let mut items = Vec::new();
+    decompiler.flush(&mut items, block);
+    decompiler.nest(items)
+}
+
+enum Frame {
+    /// Resume execution at `pc`, from `pos`.
+    Resume { pc: usize, pos: usize },
+    /// Resume execution at `pc`, from every position in the range [`pos`, `end`) where it might succeed, in order.
+    Skip { pc: usize, pos: usize, end: usize },
+    /// Restore `slot` to `pos` before resuming execution from an earlier frame.
+    Restore { slot: usize, pos: usize },
+    /// Remember that every position from `pos` onwards fails after the unbounded skip at `pc`.
+    Exhausted { pc: usize, pos: usize },
+}
+
+/// The memory needed to run a program, which can be reused between runs over the same haystack.
+#[derive(Default)]
+pub(crate) struct Scratch {
+    stack: Vec<Frame>,
+    /// For each instruction, the first position from which everything after it is known to fail,
+    /// or `None` if that can't be remembered. Empty if nothing is remembered at all.
+    exhausted: Vec<Option<usize>>,
+    /// The choices already tried during the current run, as pairs of their instruction and position,
+    /// or `None` if trying a choice again might succeed because the program refers back to a capture.
+    visited: Option<HashSet<(usize, usize)>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ProgramRef<'a> {
+    instructions: &'a [RawInstruction],
+    anchor: Option<RawAnchor>,
+    buffer: PatternRef<'a>,
+}
+
+impl<'a> ProgramRef<'a> {
+    /// SAFETY: every chunk in `program` must lie within the buffers referenced by `buffer`
+    #[must_use]
+    pub(crate) unsafe fn new(program: RawProgram<'a>, buffer: PatternRef<'a>) -> Self {
+        Self {
+            instructions: program.instructions,
+            anchor: program.anchor,
+            buffer,
+        }
+    }
+
+    /// The chunk that the prefilter searches for, if there is one.
+    #[must_use]
+    pub(crate) fn anchor(&self) -> Option<RawAnchor> {
+        self.anchor
+    }
+
+    /// The needle's bytes within the chunk that the prefilter searches for, if there is one.
+    #[must_use]
+    pub(crate) fn anchor_chunk(&self) -> Option<PatternRef<'a>> {
+        // SAFETY: chunks are guaranteed to lie within the buffer
+        self.anchor
+            .map(|anchor| unsafe { self.buffer.subpattern(anchor.offset, anchor.len) })
+    }
+
+    /// The memory needed to run the program, which remembers where unbounded skips fail between runs.
+    #[must_use]
+    pub(crate) fn scratch(&self) -> Scratch {
+        let exhausted = (0..self.instructions.len())
+            .map(|pc| {
+                // what follows a skip only depends on where it skips to, unless it refers back to a capture from before the skip
+                let (before, after) = self.instructions.split_at(pc);
+                let refers_back = after.iter().any(|x| match x {
+                    RawInstruction::Backreference { slot, .. } => before.iter().any(
+                        |y| matches!(y, RawInstruction::Save { slot: saved, .. } if saved == slot),
+                    ),
+                    _ => false,
+                });
+                (!refers_back).then_some(usize::MAX)
+            })
+            .collect();
+        // without backreferences, whether execution succeeds only depends on where it is in the program and the haystack
+        let visited = self
+            .instructions
+            .iter()
+            .all(|x| !matches!(x, RawInstruction::Backreference { .. }))
+            .then(HashSet::new);
+        Scratch {
+            stack: Vec::new(),
+            exhausted,
+            visited,
+        }
+    }
+
+    /// Rebuilds nodes equivalent to those the program was compiled from, given the name of each capture.
+    #[must_use]
+    pub(crate) fn decompile(&self, names: &[Option<&str>]) -> Vec<Node> {
+        // only named wildcards are referred back to, or record the start of the same capture more than once
+        let mut starts = HashSet::new();
+        let named_wildcards = self
+            .instructions
+            .iter()
+            .filter_map(|x| match *x {
+                RawInstruction::Backreference { slot, .. } => Some(slot),
+                RawInstruction::Save { slot, .. } if slot % 2 == 1 && !starts.insert(slot) => {
+                    Some(slot)
+                }
+                _ => None,
+            })
+            .collect();
+        Decompiler::new(names, named_wildcards).sequence(*self, 0..self.instructions.len())
+    }
+
+    /// Tries to match the program against `haystack`, beginning at `start`.
+    ///
+    /// On success, yields the position one past the last byte of the match. Shorter skips,
+    /// and earlier alternatives, are preferred over longer skips and later alternatives.
+    ///
+    /// The cursor and the bounds of each capture are recorded into `slots`, which must have room for all of them,
+    /// and must begin as [`UNSET`]. `scratch` must only be reused between runs over the same haystack.
+    #[must_use]
+    pub(crate) fn run(
+        &self,
+        haystack: &[u8],
+        start: usize,
+        scratch: &mut Scratch,
+        slots: &mut [usize],
+    ) -> Option<usize> {
+        let Scratch {
+            stack,
+            exhausted,
+            visited,
+        } = scratch;
+        stack.clear();
+        if let Some(visited) = visited.as_mut() {
+            visited.clear();
+        }
+        let mut pc = 0;
+        let mut pos = start;
+        loop {
+            let instruction = self.instructions.get(pc);
+            // a choice which was already tried from the same position failed the first time, so it fails again
+            let is_choice = matches!(
+                instruction,
+                Some(RawInstruction::Skip { .. } | RawInstruction::Split { .. })
+            );
+            if is_choice
+                && visited
+                    .as_mut()
+                    .is_some_and(|visited| !visited.insert((pc, pos)))
+            {
+                (pc, pos) = self.backtrack(haystack, stack, exhausted, slots)?;
+                continue;
+            }
+
+            let advanced = match instruction {
+                None => return Some(pos),
+                Some(&RawInstruction::Chunk { offset, len }) => {
+                    // SAFETY: chunks are guaranteed to lie within the buffer
+                    let chunk = unsafe { self.buffer.subpattern(offset, len) };
+                    match haystack.get(pos..pos + len) {
+                        // SAFETY: `other` has the same length as `chunk`
+                        Some(other) if unsafe { chunk.cmpeq_unchecked(other) } => {
+                            pc += 1;
+                            pos += len;
+                            true
+                        }
+                        _ => false,
+                    }
+                }
+                Some(&RawInstruction::Skip { min, max }) => {
+                    let first = pos.saturating_add(min);
+                    let end = match (max, exhausted.get(pc).copied().flatten()) {
+                        (Some(max), _) => pos.saturating_add(max).min(haystack.len()) + 1,
+                        (None, None) => haystack.len() + 1,
+                        // positions which an earlier run already skipped to don't need to be tried again
+                        (None, Some(failed)) => {
+                            stack.push(Frame::Exhausted { pc, pos: first });
+                            failed.min(haystack.len() + 1)
+                        }
+                    };
+                    stack.push(Frame::Skip {
+                        pc: pc + 1,
+                        pos: first,
+                        end,
+                    });
+                    false
+                }
+                Some(&RawInstruction::Split { first, second }) => {
+                    stack.push(Frame::Resume { pc: second, pos });
+                    pc = first;
+                    true
+                }
+                Some(&RawInstruction::Goto { target }) => {
+                    pc = target;
+                    true
+                }
+                Some(&RawInstruction::Member { back, set }) => {
+                    pc += 1;
+                    set.contains(haystack[pos - back])
+                }
+                Some(&RawInstruction::Save { slot, back }) => {
+                    stack.push(Frame::Restore {
+                        slot,
+                        pos: slots[slot],
+                    });
+                    slots[slot] = pos - back;
+                    pc += 1;
+                    true
+                }
+                Some(&RawInstruction::Backreference { slot, back }) => {
+                    pc += 1;
+                    slots[slot] == UNSET || haystack[pos - back] == haystack[slots[slot]]
+                }
+                Some(&RawInstruction::Field { back, field }) => {
+                    pc += 1;
+                    field.contains(&haystack[pos - back..])
+                }
+            };
+
+            if !advanced {
+                (pc, pos) = self.backtrack(haystack, stack, exhausted, slots)?;
+            }
+        }
+    }
+
+    /// Pops frames off of `stack` until one says where to resume execution, restoring any slots
+    /// and remembering any exhausted skips along the way.
+    ///
+    /// Yields `None` once there is nowhere left to resume from.
+    fn backtrack(
+        &self,
+        haystack: &[u8],
+        stack: &mut Vec<Frame>,
+        exhausted: &mut [Option<usize>],
+        slots: &mut [usize],
+    ) -> Option<(usize, usize)> {
+        loop {
+            match stack.pop()? {
+                Frame::Restore { slot, pos } => slots[slot] = pos,
+                Frame::Resume { pc, pos } => return Some((pc, pos)),
+                Frame::Skip { pc, pos, end } => {
+                    if let Some(pos) = self.seek(haystack, pc, pos, end) {
+                        if pos + 1 < end {
+                            stack.push(Frame::Skip {
+                                pc,
+                                pos: pos + 1,
+                                end,
+                            });
+                        }
+                        return Some((pc, pos));
+                    }
+                }
+                Frame::Exhausted { pc, pos } => {
+                    exhausted[pc] = exhausted[pc].map(|failed| failed.min(pos));
+                }
+            }
+        }
+    }
+
+    /// The first position in the range [`pos`, `end`) where execution at `pc` might succeed.
+    ///
+    /// A chunk can only match where its first exact byte is found, so that byte is searched for instead of trying every position.
+    #[must_use]
+    fn seek(&self, haystack: &[u8], pc: usize, pos: usize, end: usize) -> Option<usize> {
+        if pos >= end {
+            return None;
+        }
+        if let Some(&RawInstruction::Chunk { offset, len }) = self.instructions.get(pc) {
+            // SAFETY: chunks are guaranteed to lie within the buffer
+            let chunk = unsafe { self.buffer.subpattern(offset, len) };
+            if let Some(index) = chunk.mask_slice().iter().position(|x| x.is_unmasked()) {
+                let window = haystack.get(pos + index..haystack.len().min(end + index))?;
+                return memchr::memchr(chunk.word_slice()[index], window).map(|found| pos + found);
+            }
+        }
+        Some(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Compiled,
+        RawAnchor,
+        RawInstruction,
+    };
+    use crate::{
+        ast::Node,
+        pattern::PatternByte,
+    };
+
+    #[test]
+    fn test_compile() {
+        let byte = |x| Node::Byte(PatternByte::exact(x));
+
+        let compiled = Compiled::from_nodes(&[
+            byte(0x11),
+            Node::Jump {
+                min: 2,
+                max: Some(2),
+            },
+            byte(0x22),
+        ]);
+        assert!(compiled.program.is_none());
+        assert_eq!(compiled.len, 4);
+        assert_eq!(compiled.bytes.len(), 4);
+
+        let compiled = Compiled::from_nodes(&[
+            byte(0x11),
+            Node::Jump {
+                min: 1000,
+                max: Some(1000),
+            },
+            byte(0x22),
+        ]);
+        assert_eq!(compiled.len, 1002);
+        assert_eq!(
+            compiled.program.unwrap().instructions,
+            [
+                RawInstruction::Chunk { offset: 0, len: 1 },
+                RawInstruction::Skip {
+                    min: 1000,
+                    max: Some(1000)
+                },
+                RawInstruction::Chunk { offset: 32, len: 1 },
+            ]
+        );
+
+        let compiled = Compiled::from_nodes(&[
+            byte(0x11),
+            Node::Jump { min: 1, max: None },
+            Node::Alternation(vec![vec![byte(0x22)], vec![byte(0x33), byte(0x44)]]),
+            byte(0x55),
+        ]);
+        assert_eq!(compiled.len, 4);
+        let program = compiled.program.unwrap();
+        assert_eq!(
+            program.anchor,
+            Some(RawAnchor {
+                offset: 0,
+                len: 1,
+                min_distance: 0,
+                max_distance: Some(0),
+            })
+        );
+        assert_eq!(
+            program.instructions,
+            [
+                RawInstruction::Chunk { offset: 0, len: 1 },
+                RawInstruction::Skip { min: 1, max: None },
+                RawInstruction::Split {
+                    first: 3,
+                    second: 5
+                },
+                RawInstruction::Chunk { offset: 32, len: 1 },
+                RawInstruction::Goto { target: 6 },
+                RawInstruction::Chunk { offset: 64, len: 2 },
+                RawInstruction::Chunk { offset: 96, len: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_anchor() {
+        let byte = |x| Node::Byte(PatternByte::exact(x));
+        let wildcard = || Node::Byte(PatternByte::WILDCARD);
+        let anchor = |nodes: &[Node]| Compiled::from_nodes(nodes).program.unwrap().anchor;
+
+        // the chunk with the most exact bytes wins
+        assert_eq!(
+            anchor(&[
+                byte(0x11),
+                wildcard(),
+                Node::Jump {
+                    min: 2,
+                    max: Some(4)
+                },
+                byte(0x22),
+                byte(0x33),
+                byte(0x44),
+            ]),
+            Some(RawAnchor {
+                offset: 32,
+                len: 3,
+                min_distance: 4,
+                max_distance: Some(6),
+            })
+        );
+
+        // ties go to the earliest chunk
+        assert_eq!(
+            anchor(&[
+                byte(0x11),
+                Node::Jump {
+                    min: 0,
+                    max: Some(1)
+                },
+                byte(0x22),
+            ]),
+            Some(RawAnchor {
+                offset: 0,
+                len: 1,
+                min_distance: 0,
+                max_distance: Some(0),
+            })
+        );
+
+        // alternations are skipped over, but still count towards the distance
+        assert_eq!(
+            anchor(&[
+                Node::Alternation(vec![
+                    vec![byte(0x11), byte(0x22), byte(0x33)],
+                    vec![byte(0x44)],
+                ]),
+                wildcard(),
+                byte(0x55),
+            ]),
+            Some(RawAnchor {
+                offset: 64,
+                len: 2,
+                min_distance: 1,
+                max_distance: Some(3),
+            })
+        );
+
+        // bounded distances are preferred over unbounded ones
+        assert_eq!(
+            anchor(&[
+                wildcard(),
+                byte(0x11),
+                Node::Jump { min: 0, max: None },
+                byte(0x22),
+                byte(0x33),
+            ]),
+            Some(RawAnchor {
+                offset: 0,
+                len: 2,
+                min_distance: 0,
+                max_distance: Some(0),
+            })
+        );
+
+        // chunks without any exact bytes can't be searched for
+        assert_eq!(
+            anchor(&[wildcard(), Node::Jump { min: 1, max: None }]),
+            None
+        );
+    }
+}
@@ -0,0 +1,13 @@
+/// A haystack of `len` bytes picked pseudo-randomly from `alphabet`, the same on every run.
+///
+/// A small alphabet makes any needle built from it match often.
+#[must_use]
+pub(crate) fn haystack(alphabet: &[u8], len: usize) -> Vec<u8> {
+    let mut state = 0x1234_5678u32;
+    std::iter::repeat_with(|| {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        alphabet[(state >> 16) as usize % alphabet.len()]
+    })
+    .take(len)
+    .collect()
+}
@@ -4,7 +4,11 @@ use aob_common::{
     DynamicNeedle,
     Error as AobError,
     Needle as _,
+    RawAnchor,
+    RawField,
+    RawInstruction,
     RawPrefilter,
+    RawScalar,
 };
 use ariadne::{
     Config,
@@ -32,6 +36,7 @@ use syn::{
     },
     parse_macro_input,
     Ident,
+    LitByteStr,
     LitStr,
     Token,
     Visibility,
@@ -58,8 +63,13 @@ macro_rules! unsuffixed_primitive {
 unsuffixed_primitive!(UnsuffixedUsize: usize => usize_unsuffixed);
 unsuffixed_primitive!(UnsuffixedU8: u8 => u8_unsuffixed);
 
+#[derive(Clone, Copy)]
 enum Method {
     Ida,
+    Code,
+    Yara,
+    Array,
+    Bytes,
 }
 
 impl TryFrom<Ident> for Method {
@@ -68,7 +78,64 @@ impl TryFrom<Ident> for Method {
     fn try_from(value: Ident) -> Result<Self, Self::Error> {
         match value.to_string().as_str() {
             "ida" => Ok(Self::Ida),
-            _ => Err(syn::Error::new(value.span(), "expected one of: `ida`")),
+            "code" => Ok(Self::Code),
+            "yara" => Ok(Self::Yara),
+            "array" => Ok(Self::Array),
+            "bytes" => Ok(Self::Bytes),
+            _ => Err(syn::Error::new(
+                value.span(),
+                "expected one of: `ida`, `code`, `yara`, `array`, `bytes`",
+            )),
+        }
+    }
+}
+
+enum Pattern {
+    Ida(String),
+    Code { bytes: Vec<u8>, mask: String },
+    Yara(String),
+    Array(String),
+    Bytes { bytes: Vec<u8>, source: String },
+}
+
+impl Pattern {
+    fn parse(method: Method, input: ParseStream) -> ParseResult<Self> {
+        match method {
+            Method::Ida => Ok(Self::Ida(input.parse::<LitStr>()?.value())),
+            Method::Code => {
+                let bytes = input.parse::<LitByteStr>()?.value();
+                input.parse::<Token![,]>()?;
+                let mask = input.parse::<LitStr>()?.value();
+                Ok(Self::Code { bytes, mask })
+            }
+            Method::Yara => Ok(Self::Yara(input.parse::<LitStr>()?.value())),
+            Method::Array => Ok(Self::Array(input.parse::<LitStr>()?.value())),
+            Method::Bytes => {
+                let bytes = input.parse::<LitByteStr>()?.value();
+                // errors count each byte as a single character
+                let source = bytes.iter().copied().map(char::from).collect();
+                Ok(Self::Bytes { bytes, source })
+            }
+        }
+    }
+
+    /// The text that any parsing errors will refer to.
+    #[must_use]
+    fn source(&self) -> &str {
+        match self {
+            Self::Ida(pattern) | Self::Yara(pattern) | Self::Array(pattern) => pattern,
+            Self::Code { mask, .. } => mask,
+            Self::Bytes { source, .. } => source,
+        }
+    }
+
+    fn to_needle(&self) -> Result<DynamicNeedle, AobError<'_>> {
+        match self {
+            Self::Ida(pattern) => DynamicNeedle::from_ida(pattern),
+            Self::Code { bytes, mask } => DynamicNeedle::from_code_style(bytes, mask),
+            Self::Yara(pattern) => DynamicNeedle::from_yara(pattern),
+            Self::Array(pattern) => DynamicNeedle::from_array_literal(pattern),
+            Self::Bytes { bytes, .. } => DynamicNeedle::from_byte_string(bytes),
         }
     }
 }
@@ -76,18 +143,13 @@ impl TryFrom<Ident> for Method {
 struct AobDecl {
     visibility: Visibility,
     name: Ident,
-    method: Method,
-    pattern: String,
+    pattern: Pattern,
 }
 
 impl AobDecl {
     #[must_use]
     fn into_tokens(self) -> TokenStream2 {
-        let parse_result = match self.method {
-            Method::Ida => DynamicNeedle::from_ida(self.pattern.as_str()),
-        };
-
-        match parse_result {
+        match self.pattern.to_needle() {
             Ok(needle) => self.tokenize_needle(&needle),
             Err(error) => self.tokenize_error(&error),
         }
@@ -139,16 +201,183 @@ impl AobDecl {
         let word = tokenize_slice(needle.serialize_word());
         let mask = tokenize_slice(needle.serialize_mask());
 
+        let program = Self::tokenize_option(needle.serialize_program(), |program| {
+            let instructions = program
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    let instruction = Self::tokenize_instruction(*instruction);
+                    quote::quote!(#instruction,)
+                })
+                .collect::<TokenStream2>();
+            let anchor = Self::tokenize_option(program.anchor, Self::tokenize_anchor);
+            quote::quote! {
+                ::aob_common::RawProgram {
+                    instructions: &[#instructions],
+                    anchor: #anchor,
+                }
+            }
+        });
+
+        let capture_names = needle
+            .serialize_capture_names()
+            .into_iter()
+            .map(|name| {
+                let name = Self::tokenize_option(name, |x| quote::quote!(#x));
+                quote::quote!(#name,)
+            })
+            .collect::<TokenStream2>();
+        let capture_offsets = needle.serialize_capture_offsets();
+        let backreferences = needle
+            .serialize_backreferences()
+            .iter()
+            .map(|(first, second)| quote::quote!((#first, #second),))
+            .collect::<TokenStream2>();
+        let fields = needle
+            .serialize_fields()
+            .iter()
+            .map(|&(offset, field)| {
+                let field = Self::tokenize_field(field);
+                quote::quote!((#offset, #field),)
+            })
+            .collect::<TokenStream2>();
+        let captures = quote::quote! {
+            ::aob_common::RawCaptures {
+                names: &[#capture_names],
+                offsets: &[#(#capture_offsets),*],
+                backreferences: &[#backreferences],
+                fields: &[#fields],
+            }
+        };
         let Self {
             visibility, name, ..
         } = self;
 
         quote::quote! {
             #visibility const #name: ::aob_common::StaticNeedle<#needle_len, #buffer_len> =
-                ::aob_common::StaticNeedle::new(#prefilter, [#word], [#mask]);
+                ::aob_common::StaticNeedle::new(#prefilter, [#word], [#mask], #program, #captures);
         }
     }
 
+    #[must_use]
+    fn tokenize_instruction(instruction: RawInstruction) -> TokenStream2 {
+        match instruction {
+            RawInstruction::Chunk { offset, len } => quote::quote! {
+                ::aob_common::RawInstruction::Chunk {
+                    offset: #offset,
+                    len: #len,
+                }
+            },
+            RawInstruction::Skip { min, max } => {
+                let max = Self::tokenize_option(max, |max| quote::quote!(#max));
+                quote::quote! {
+                    ::aob_common::RawInstruction::Skip {
+                        min: #min,
+                        max: #max,
+                    }
+                }
+            }
+            RawInstruction::Split { first, second } => quote::quote! {
+                ::aob_common::RawInstruction::Split {
+                    first: #first,
+                    second: #second,
+                }
+            },
+            RawInstruction::Goto { target } => quote::quote! {
+                ::aob_common::RawInstruction::Goto {
+                    target: #target,
+                }
+            },
+            RawInstruction::Member { back, set } => {
+                let bits = set.bits;
+                quote::quote! {
+                    ::aob_common::RawInstruction::Member {
+                        back: #back,
+                        set: ::aob_common::RawByteSet {
+                            bits: [#(#bits),*],
+                        },
+                    }
+                }
+            }
+            RawInstruction::Save { slot, back } => quote::quote! {
+                ::aob_common::RawInstruction::Save {
+                    slot: #slot,
+                    back: #back,
+                }
+            },
+            RawInstruction::Backreference { slot, back } => quote::quote! {
+                ::aob_common::RawInstruction::Backreference {
+                    slot: #slot,
+                    back: #back,
+                }
+            },
+            RawInstruction::Field { back, field } => {
+                let field = Self::tokenize_field(field);
+                quote::quote! {
+                    ::aob_common::RawInstruction::Field {
+                        back: #back,
+                        field: #field,
+                    }
+                }
+            }
+        }
+    }
+
+    #[must_use]
+    fn tokenize_field(field: RawField) -> TokenStream2 {
+        let RawField {
+            scalar,
+            len,
+            big_endian,
+            min,
+            max,
+        } = field;
+        let scalar = match scalar {
+            RawScalar::Unsigned => quote::quote!(Unsigned),
+            RawScalar::Signed => quote::quote!(Signed),
+            RawScalar::Float => quote::quote!(Float),
+        };
+        quote::quote! {
+            ::aob_common::RawField {
+                scalar: ::aob_common::RawScalar::#scalar,
+                len: #len,
+                big_endian: #big_endian,
+                min: #min,
+                max: #max,
+            }
+        }
+    }
+
+    #[must_use]
+    fn tokenize_anchor(anchor: RawAnchor) -> TokenStream2 {
+        let RawAnchor {
+            offset,
+            len,
+            min_distance,
+            max_distance,
+        } = anchor;
+        let max_distance = Self::tokenize_option(max_distance, |x| quote::quote!(#x));
+        quote::quote! {
+            ::aob_common::RawAnchor {
+                offset: #offset,
+                len: #len,
+                min_distance: #min_distance,
+                max_distance: #max_distance,
+            }
+        }
+    }
+
+    #[must_use]
+    fn tokenize_option<T>(value: Option<T>, f: impl FnOnce(T) -> TokenStream2) -> TokenStream2 {
+        value.map_or_else(
+            || quote::quote!(::core::option::Option::None),
+            |x| {
+                let x = f(x);
+                quote::quote!(::core::option::Option::Some(#x))
+            },
+        )
+    }
+
     #[must_use]
     fn tokenize_error(&self, error: &AobError) -> TokenStream2 {
         let mut buffer = Vec::new();
@@ -157,7 +386,7 @@ impl AobDecl {
             .with_message(error.to_string())
             .with_label(Label::new(error.span()).with_message(error.reason().to_string()))
             .finish()
-            .write(Source::from(&self.pattern), &mut buffer)
+            .write(Source::from(self.pattern.source()), &mut buffer)
             .unwrap();
         let error_message = String::from_utf8(buffer).unwrap();
         quote::quote_spanned!(Span::call_site() => compile_error!(#error_message))
@@ -174,13 +403,12 @@ impl Parse for AobDecl {
         let pattern = {
             let content;
             parenthesized!(content in input);
-            content.parse::<LitStr>()?.value()
+            Pattern::parse(method, &content)?
         };
         input.parse::<Token![;]>()?;
         Ok(Self {
             visibility,
             name,
-            method,
             pattern,
         })
     }
@@ -222,13 +450,17 @@ impl Parse for AobDecls {
 ///     [pub] const NAME_N = METHOD_N("PATTERN_N");
 /// }
 /// ```
-/// Expects syntax of the form: `$VISIBILITY? const $IDENTIFIER = $METHOD("$PATTERN");`
+/// Expects syntax of the form: `$VISIBILITY? const $IDENTIFIER = $METHOD($PATTERN);`
 ///
 /// With the following rules:
 /// * `$VISIBILITY` is a valid [Visibility](<https://doc.rust-lang.org/reference/visibility-and-privacy.html>) token, or nothing.
 /// * `$IDENTIFIER` is a valid [Identifier](<https://doc.rust-lang.org/reference/identifiers.html>) token.
 /// * `$METHOD` is one of:
-///   * `ida`.
+///   * `ida`, which expects a string literal (see [`DynamicNeedle::from_ida`](aob_common::DynamicNeedle::from_ida)).
+///   * `code`, which expects a byte string literal followed by a string literal mask (see [`DynamicNeedle::from_code_style`](aob_common::DynamicNeedle::from_code_style)).
+///   * `yara`, which expects a string literal (see [`DynamicNeedle::from_yara`](aob_common::DynamicNeedle::from_yara)).
+///   * `array`, which expects a string literal (see [`DynamicNeedle::from_array_literal`](aob_common::DynamicNeedle::from_array_literal)).
+///   * `bytes`, which expects a byte string literal (see [`DynamicNeedle::from_byte_string`](aob_common::DynamicNeedle::from_byte_string)).
 /// * `$PATTERN` is a valid pattern whose syntax depends on the chosen `$METHOD`.
 ///
 /// ## Example
@@ -237,10 +469,28 @@ impl Parse for AobDecls {
 /// # use aob_common::Needle as _;
 /// aob! {
 ///     const NEEDLE = ida("78 ? BC");
+///     const CODE = code(b"\x78\x00\xBC", "x?x");
+///     const YARA = yara("{ 56 [1-2] BC }");
+///     const ARRAY = array("{ 0x78, ?, 0xBC }");
+///     const BYTES = bytes(br"x\?z");
+///     const LINES = ida("
+///         34 56   ; the first two bytes
+///         ? ? BC  // followed by the rest
+///     ");
 /// }
 /// let haystack = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE];
 /// let matched = NEEDLE.find(&haystack).unwrap();
 /// assert_eq!(matched.as_bytes(), [0x78, 0x9A, 0xBC]);
+/// let matched = CODE.find(&haystack).unwrap();
+/// assert_eq!(matched.as_bytes(), [0x78, 0x9A, 0xBC]);
+/// let matched = YARA.find(&haystack).unwrap();
+/// assert_eq!(matched.as_bytes(), [0x56, 0x78, 0x9A, 0xBC]);
+/// let matched = ARRAY.find(&haystack).unwrap();
+/// assert_eq!(matched.as_bytes(), [0x78, 0x9A, 0xBC]);
+/// let matched = BYTES.find(b"wxyz").unwrap();
+/// assert_eq!(matched.as_bytes(), b"xyz");
+/// let matched = LINES.find(&haystack).unwrap();
+/// assert_eq!(matched.as_bytes(), [0x34, 0x56, 0x78, 0x9A, 0xBC]);
 /// ```
 #[proc_macro]
 pub fn aob(input: TokenStream) -> TokenStream {